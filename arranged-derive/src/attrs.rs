@@ -0,0 +1,26 @@
+use proc_macro2::TokenStream;
+use syn::{Attribute, Result, Type};
+
+// Finds the layout requested by `#[arranged(layout = ...)]`, falling back to
+// `Flat` when the field has no such attribute.
+pub fn field_layout(attrs: &[Attribute]) -> Result<TokenStream> {
+    let mut layout = None;
+    for attr in attrs {
+        if !attr.path().is_ident("arranged") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("layout") {
+                if layout.is_some() {
+                    return Err(meta.error("duplicate `layout` attribute"));
+                }
+                let ty: Type = meta.value()?.parse()?;
+                layout = Some(quote!(#ty));
+                Ok(())
+            } else {
+                Err(meta.error("unknown `arranged` attribute"))
+            }
+        })?;
+    }
+    Ok(layout.unwrap_or_else(|| quote!(::arranged::layouts::Flat)))
+}
@@ -0,0 +1,336 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use syn::{DataEnum, DeriveInput, Error, Fields, Index, Result};
+
+// Converts a `CamelCase` variant name to `snake_case`.
+fn snake_case(name: &Ident) -> String {
+    let mut snake = String::new();
+    for (i, ch) in name.to_string().chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+pub fn expand(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "ArrayLayout cannot be derived for generic enums"));
+    }
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(&input.ident, "ArrayLayout cannot be derived for enums without variants"));
+    }
+    if data.variants.len() > 256 {
+        return Err(Error::new_spanned(&input.ident, "ArrayLayout can only be derived for enums with at most 256 variants"));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let layout_name = format_ident!("{}Layout", name);
+    let ptr_name = format_ident!("{}LayoutPtr", name);
+    let info_name = format_ident!("{}LayoutInfo", name);
+    let payload_name = format_ident!("{}LayoutPayload", name);
+    let tag_layout = quote!(<::arranged::layouts::Flat as ::arranged::layouts::ArrayLayout<u8>>);
+    let payload_layout = quote!(<::arranged::layouts::Flat as ::arranged::layouts::ArrayLayout<#payload_name>>);
+
+    let variants_trait = format_ident!("{}Variants", name);
+    let variants_mut_trait = format_ident!("{}VariantsMut", name);
+
+    // Elements are split into a tag column and a payload column. The payload
+    // is a union with one member per variant holding that variant's fields,
+    // so an element takes a tag byte plus room for its largest variant, much
+    // like the enum itself. Fields therefore always use the `Flat` layout.
+    let mut prefixes: Vec<(String, &Ident)> = Vec::new();
+    let mut payload_fields = Vec::new();
+    let mut payload_types = Vec::new();
+    let mut tag_names = Vec::new();
+    let mut tags = Vec::new();
+    let mut read_arms = Vec::new();
+    let mut write_arms = Vec::new();
+    let mut drop_arms = Vec::new();
+    let mut refs_structs = Vec::new();
+    let mut accessor_names = Vec::new();
+    let mut accessor_refs = Vec::new();
+    let mut accessor_refs_mut = Vec::new();
+    let mut accessor_tags = Vec::new();
+    let mut accessor_constructs = Vec::new();
+    let mut accessor_constructs_mut = Vec::new();
+
+    for (index, variant) in data.variants.iter().enumerate() {
+        let tag = index as u8;
+        let variant_name = &variant.ident;
+        let prefix = snake_case(variant_name);
+        if let Some(&(_, other)) = prefixes.iter().find(|entry| entry.0 == prefix) {
+            return Err(Error::new_spanned(variant_name, format!("variant `{}` has the same snake_case name `{}` as variant `{}`", variant_name, prefix, other)));
+        }
+        prefixes.push((prefix.clone(), variant_name));
+        tag_names.push(Ident::new(&prefix.to_uppercase(), variant_name.span()));
+        tags.push(tag);
+
+        for field in variant.fields.iter() {
+            if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("arranged")) {
+                return Err(Error::new_spanned(attr, "enum variant fields share one payload column and cannot have their own layout"));
+            }
+        }
+        let types: Vec<_> = variant.fields.iter().map(|field| &field.ty).collect();
+        let indices: Vec<_> = (0..types.len()).map(Index::from).collect();
+        let value_vars: Vec<_> = (0..types.len()).map(|i| Ident::new(&format!("value_{}", i), Span::call_site())).collect();
+
+        let (pattern, construct) = match variant.fields {
+            Fields::Named(_) => {
+                let field_names: Vec<_> = variant.fields.iter().map(|field| field.ident.clone().unwrap()).collect();
+                (
+                    quote!(#name::#variant_name { #(#field_names: #value_vars),* }),
+                    quote!(#name::#variant_name { #(#field_names: #value_vars),* })
+                )
+            },
+            Fields::Unnamed(_) => (
+                quote!(#name::#variant_name(#(#value_vars),*)),
+                quote!(#name::#variant_name(#(#value_vars),*))
+            ),
+            Fields::Unit => (quote!(#name::#variant_name), quote!(#name::#variant_name))
+        };
+
+        if variant.fields.is_empty() {
+            read_arms.push(quote!(#tag => #construct));
+            write_arms.push(quote!(#pattern => {
+                #tag_layout::write(ptr.tag, #tag);
+            }));
+            drop_arms.push(quote!(#tag => { }));
+            continue;
+        }
+
+        let payload_field = format_ident!("{}_fields", prefix);
+        // The fields of this variant inside the payload at `ptr`. The
+        // `ManuallyDrop` wrapper is transparent, so this is just a cast.
+        let fields_ptr = quote!((::core::ptr::addr_of_mut!((*ptr.payload.as_ptr()).#payload_field) as *mut (#(#types,)*)));
+
+        read_arms.push(quote!(#tag => {
+            let (#(#value_vars,)*) = ::core::ptr::read(#fields_ptr);
+            #construct
+        }));
+        write_arms.push(quote!(#pattern => {
+            #tag_layout::write(ptr.tag, #tag);
+            ::core::ptr::write(#fields_ptr, (#(#value_vars,)*));
+        }));
+        drop_arms.push(quote!(#tag => {
+            ::core::ptr::drop_in_place(#fields_ptr);
+        }));
+
+        // Safe access to the fields of one variant, checked against the tag.
+        // Unit variants have nothing to access beyond the tag itself.
+        let refs_name = format_ident!("{}{}Refs", name, variant_name);
+        let refs_mut_name = format_ident!("{}{}RefsMut", name, variant_name);
+        let field_ptrs: Vec<_> = indices.iter().map(|index| {
+            quote!(::core::ptr::NonNull::new_unchecked(::core::ptr::addr_of_mut!((*#fields_ptr).#index)))
+        }).collect();
+        match variant.fields {
+            Fields::Named(_) => {
+                let field_names: Vec<_> = variant.fields.iter().map(|field| field.ident.clone().unwrap()).collect();
+                refs_structs.push(quote! {
+                    #vis struct #refs_name<'a> {
+                        #(pub #field_names: ::arranged::Ref<'a, #types>),*
+                    }
+
+                    #vis struct #refs_mut_name<'a> {
+                        #(pub #field_names: ::arranged::RefMut<'a, #types>),*
+                    }
+                });
+                accessor_constructs.push(quote!(#refs_name { #(#field_names: ::arranged::Ref::from_raw(#field_ptrs)),* }));
+                accessor_constructs_mut.push(quote!(#refs_mut_name { #(#field_names: ::arranged::RefMut::from_raw(#field_ptrs)),* }));
+            },
+            _ => {
+                refs_structs.push(quote! {
+                    #vis struct #refs_name<'a>(#(pub ::arranged::Ref<'a, #types>),*);
+
+                    #vis struct #refs_mut_name<'a>(#(pub ::arranged::RefMut<'a, #types>),*);
+                });
+                accessor_constructs.push(quote!(#refs_name(#(::arranged::Ref::from_raw(#field_ptrs)),*)));
+                accessor_constructs_mut.push(quote!(#refs_mut_name(#(::arranged::RefMut::from_raw(#field_ptrs)),*)));
+            }
+        }
+        accessor_names.push(format_ident!("as_{}", prefix));
+        accessor_refs.push(refs_name);
+        accessor_refs_mut.push(refs_mut_name);
+        accessor_tags.push(tag);
+
+        payload_fields.push(payload_field);
+        payload_types.push(quote!((#(#types,)*)));
+    }
+
+    Ok(quote! {
+        #vis struct #layout_name {
+            _priv: ()
+        }
+
+        impl #layout_name {
+            #(pub const #tag_names: u8 = #tags;)*
+        }
+
+        #[allow(dead_code)]
+        #vis union #payload_name {
+            _unit: (),
+            #(#payload_fields: ::core::mem::ManuallyDrop<#payload_types>),*
+        }
+
+        #vis struct #ptr_name {
+            pub tag: ::core::ptr::NonNull<u8>,
+            pub payload: ::core::ptr::NonNull<#payload_name>
+        }
+
+        impl ::core::marker::Copy for #ptr_name { }
+        impl ::core::clone::Clone for #ptr_name {
+            fn clone(&self) -> Self { *self }
+        }
+
+        #vis struct #info_name {
+            tag_offset: usize
+        }
+
+        unsafe impl ::arranged::layouts::ArrayLayout<#name> for #layout_name {
+            type Ptr = #ptr_name;
+            type ArrayInfo = #info_name;
+
+            // The payload goes first, so that the tag bytes do not push it
+            // out of alignment.
+            fn layout_array(count: usize) -> (::core::alloc::Layout, #info_name) {
+                let (payload_layout, ()) = #payload_layout::layout_array(count);
+                let (tag_layout, ()) = #tag_layout::layout_array(count);
+                let (layout, tag_offset) = payload_layout.extend(tag_layout).expect("Overflow in combining array layouts");
+                (layout, #info_name { tag_offset: tag_offset })
+            }
+
+            unsafe fn from_flat_ptr(ptr: ::core::ptr::NonNull<u8>, info: #info_name) -> #ptr_name {
+                #ptr_name {
+                    tag: ::core::ptr::NonNull::new_unchecked(ptr.as_ptr().add(info.tag_offset)),
+                    payload: ptr.cast()
+                }
+            }
+
+            unsafe fn initialize(_ptr: #ptr_name, _count: usize) { }
+
+            unsafe fn base_ptr(ptr: #ptr_name, _info: #info_name) -> ::core::ptr::NonNull<u8> {
+                ptr.payload.cast()
+            }
+
+            fn dangling() -> #ptr_name {
+                #ptr_name { tag: #tag_layout::dangling(), payload: #payload_layout::dangling() }
+            }
+
+            unsafe fn offset(ptr: #ptr_name, offset: isize) -> #ptr_name {
+                #ptr_name { tag: #tag_layout::offset(ptr.tag, offset), payload: #payload_layout::offset(ptr.payload, offset) }
+            }
+
+            unsafe fn same_ptr(ptr1: #ptr_name, ptr2: #ptr_name) -> bool {
+                // The tag column is never zero-sized, so it alone decides
+                #tag_layout::same_ptr(ptr1.tag, ptr2.tag)
+            }
+
+            unsafe fn read(ptr: #ptr_name) -> #name {
+                match #tag_layout::read(ptr.tag) {
+                    #(#read_arms,)*
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::hint::unreachable_unchecked()
+                }
+            }
+
+            unsafe fn write(ptr: #ptr_name, value: #name) {
+                match value {
+                    #(#write_arms)*
+                }
+            }
+
+            unsafe fn drop_in_place(ptr: #ptr_name) {
+                match #tag_layout::read(ptr.tag) {
+                    #(#drop_arms)*
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::hint::unreachable_unchecked()
+                }
+            }
+
+            // Moving an element never needs to look at its tag: both columns
+            // move as raw bytes, whichever variant they hold.
+
+            unsafe fn copy_one_nonoverlapping(src: #ptr_name, dest: #ptr_name) {
+                #tag_layout::copy_one_nonoverlapping(src.tag, dest.tag);
+                #payload_layout::copy_one_nonoverlapping(src.payload, dest.payload);
+            }
+
+            unsafe fn swap_one_nonoverlapping(ptr1: #ptr_name, ptr2: #ptr_name) {
+                #tag_layout::swap_one_nonoverlapping(ptr1.tag, ptr2.tag);
+                #payload_layout::swap_one_nonoverlapping(ptr1.payload, ptr2.payload);
+            }
+
+            unsafe fn copy_leftwards(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #tag_layout::copy_leftwards(src.tag, dest.tag, count);
+                #payload_layout::copy_leftwards(src.payload, dest.payload, count);
+            }
+
+            unsafe fn copy_rightwards(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #tag_layout::copy_rightwards(src.tag, dest.tag, count);
+                #payload_layout::copy_rightwards(src.payload, dest.payload, count);
+            }
+
+            unsafe fn copy_nonoverlapping(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #tag_layout::copy_nonoverlapping(src.tag, dest.tag, count);
+                #payload_layout::copy_nonoverlapping(src.payload, dest.payload, count);
+            }
+
+            unsafe fn swap_nonoverlapping(ptr1: #ptr_name, ptr2: #ptr_name, count: usize) {
+                #tag_layout::swap_nonoverlapping(ptr1.tag, ptr2.tag, count);
+                #payload_layout::swap_nonoverlapping(ptr1.payload, ptr2.payload, count);
+            }
+        }
+
+        // See the struct derive: only `Copy` enums can be lent out.
+        unsafe impl ::arranged::layouts::Inspect<#name> for #layout_name where for<'x> #name: ::core::marker::Copy {
+            unsafe fn inspect<R, F: FnOnce(&#name) -> R>(ptr: #ptr_name, func: F) -> R {
+                func(&<Self as ::arranged::layouts::ArrayLayout<#name>>::read(ptr))
+            }
+        }
+
+        unsafe impl ::arranged::layouts::EnumLayout<#name> for #layout_name {
+            fn tag_ptr(ptr: #ptr_name) -> ::core::ptr::NonNull<u8> {
+                ptr.tag
+            }
+        }
+
+        #(#refs_structs)*
+
+        #vis trait #variants_trait<'a> {
+            #(fn #accessor_names(self) -> ::core::option::Option<#accessor_refs<'a>>;)*
+        }
+
+        #vis trait #variants_mut_trait<'a> {
+            #(fn #accessor_names(self) -> ::core::option::Option<#accessor_refs_mut<'a>>;)*
+        }
+
+        impl<'a> #variants_trait<'a> for ::arranged::Ref<'a, #name, #layout_name> {
+            #(
+                fn #accessor_names(self) -> ::core::option::Option<#accessor_refs<'a>> {
+                    if self.tag() != #accessor_tags {
+                        return ::core::option::Option::None;
+                    }
+                    let ptr = self.as_raw();
+                    unsafe { ::core::option::Option::Some(#accessor_constructs) }
+                }
+            )*
+        }
+
+        impl<'a> #variants_mut_trait<'a> for ::arranged::RefMut<'a, #name, #layout_name> {
+            #(
+                fn #accessor_names(self) -> ::core::option::Option<#accessor_refs_mut<'a>> {
+                    if self.tag() != #accessor_tags {
+                        return ::core::option::Option::None;
+                    }
+                    let ptr = self.as_raw();
+                    unsafe { ::core::option::Option::Some(#accessor_constructs_mut) }
+                }
+            )*
+        }
+    })
+}
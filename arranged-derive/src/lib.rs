@@ -0,0 +1,23 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+mod attrs;
+mod enums;
+mod structs;
+
+use proc_macro::TokenStream;
+use syn::{Data, DeriveInput};
+
+#[proc_macro_derive(ArrayLayout, attributes(arranged))]
+pub fn derive_array_layout(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    let expanded = match input.data {
+        Data::Struct(ref data) => structs::expand(&input, data),
+        Data::Enum(ref data) => enums::expand(&input, data),
+        Data::Union(_) => Err(syn::Error::new_spanned(&input.ident, "ArrayLayout cannot be derived for unions"))
+    };
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
+}
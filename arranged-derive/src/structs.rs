@@ -0,0 +1,219 @@
+use proc_macro2::TokenStream;
+use syn::{DataStruct, DeriveInput, Error, Fields, Result};
+
+use attrs;
+
+pub fn expand(input: &DeriveInput, data: &DataStruct) -> Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "ArrayLayout cannot be derived for generic structs"));
+    }
+    let fields = match data.fields {
+        Fields::Named(ref fields) if !fields.named.is_empty() => &fields.named,
+        _ => return Err(Error::new_spanned(&input.ident, "ArrayLayout can only be derived for structs with named fields"))
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let layout_name = format_ident!("{}Layout", name);
+    let ptr_name = format_ident!("{}LayoutPtr", name);
+    let info_name = format_ident!("{}LayoutInfo", name);
+    let columns_name = format_ident!("{}Columns", name);
+    let columns_mut_name = format_ident!("{}ColumnsMut", name);
+    let slice_trait = format_ident!("{}Slice", name);
+    let slice_mut_trait = format_ident!("{}SliceMut", name);
+
+    let names: Vec<_> = fields.iter().map(|field| field.ident.clone().unwrap()).collect();
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let layouts = fields.iter().map(|field| attrs::field_layout(&field.attrs)).collect::<Result<Vec<_>>>()?;
+
+    // `<FieldLayout as ArrayLayout<FieldType>>`, the path through which every
+    // per-field operation is dispatched.
+    let field_layouts: Vec<_> = types.iter().zip(&layouts).map(|(ty, layout)| {
+        quote!(<#layout as ::arranged::layouts::ArrayLayout<#ty>>)
+    }).collect();
+    let layout_vars: Vec<_> = (0..names.len()).map(|i| format_ident!("layout_{}", i)).collect();
+    let info_vars: Vec<_> = (0..names.len()).map(|i| format_ident!("info_{}", i)).collect();
+    let offset_vars: Vec<_> = (0..names.len()).map(|i| format_ident!("offset_{}", i)).collect();
+    let value_vars: Vec<_> = (0..names.len()).map(|i| format_ident!("value_{}", i)).collect();
+
+    let first_name = &names[0];
+    let first_layout = &field_layouts[0];
+
+    // Like `Parallel`, ask the first column with nonzero-sized elements, since
+    // zero-sized columns may spuriously report equality.
+    let last_layout = &field_layouts[names.len() - 1];
+    let last_name = &names[names.len() - 1];
+    let same_ptr = names.iter().zip(&types).zip(&field_layouts).rev().skip(1).fold(
+        quote!(#last_layout::same_ptr(ptr1.#last_name, ptr2.#last_name)),
+        |rest, ((name, ty), layout)| quote! {
+            if ::core::mem::size_of::<#ty>() > 0 {
+                #layout::same_ptr(ptr1.#name, ptr2.#name)
+            } else {
+                #rest
+            }
+        }
+    );
+
+    Ok(quote! {
+        #vis struct #layout_name {
+            _priv: ()
+        }
+
+        #vis struct #ptr_name {
+            #(pub #names: #field_layouts::Ptr),*
+        }
+
+        impl ::core::marker::Copy for #ptr_name { }
+        impl ::core::clone::Clone for #ptr_name {
+            fn clone(&self) -> Self { *self }
+        }
+
+        #vis struct #info_name {
+            #(#names: (#field_layouts::ArrayInfo, usize)),*
+        }
+
+        unsafe impl ::arranged::layouts::ArrayLayout<#name> for #layout_name {
+            type Ptr = #ptr_name;
+            type ArrayInfo = #info_name;
+
+            fn layout_array(count: usize) -> (::core::alloc::Layout, #info_name) {
+                let layout = ::core::alloc::Layout::new::<()>();
+                #(
+                    let (#layout_vars, #info_vars) = #field_layouts::layout_array(count);
+                    let (layout, #offset_vars) = layout.extend(#layout_vars).expect("Overflow in combining array layouts");
+                )*
+                (layout, #info_name { #(#names: (#info_vars, #offset_vars)),* })
+            }
+
+            unsafe fn from_flat_ptr(ptr: ::core::ptr::NonNull<u8>, info: #info_name) -> #ptr_name {
+                #ptr_name { #(
+                    #names: #field_layouts::from_flat_ptr(::core::ptr::NonNull::new_unchecked(ptr.as_ptr().add(info.#names.1)), info.#names.0)
+                ),* }
+            }
+
+            unsafe fn initialize(ptr: #ptr_name, count: usize) {
+                #(#field_layouts::initialize(ptr.#names, count);)*
+            }
+
+            unsafe fn base_ptr(ptr: #ptr_name, info: #info_name) -> ::core::ptr::NonNull<u8> {
+                #first_layout::base_ptr(ptr.#first_name, info.#first_name.0)
+            }
+
+            fn dangling() -> #ptr_name {
+                #ptr_name { #(#names: #field_layouts::dangling()),* }
+            }
+
+            unsafe fn offset(ptr: #ptr_name, offset: isize) -> #ptr_name {
+                #ptr_name { #(#names: #field_layouts::offset(ptr.#names, offset)),* }
+            }
+
+            unsafe fn same_ptr(ptr1: #ptr_name, ptr2: #ptr_name) -> bool {
+                #same_ptr
+            }
+
+            unsafe fn read(ptr: #ptr_name) -> #name {
+                #name { #(#names: #field_layouts::read(ptr.#names)),* }
+            }
+
+            unsafe fn write(ptr: #ptr_name, value: #name) {
+                let #name { #(#names: #value_vars),* } = value;
+                #(#field_layouts::write(ptr.#names, #value_vars);)*
+            }
+
+            unsafe fn drop_in_place(ptr: #ptr_name) {
+                #(#field_layouts::drop_in_place(ptr.#names);)*
+            }
+
+            fn can_store(value: &#name) -> bool {
+                true #(&& #field_layouts::can_store(&value.#names))*
+            }
+
+            unsafe fn copy_one_nonoverlapping(src: #ptr_name, dest: #ptr_name) {
+                #(#field_layouts::copy_one_nonoverlapping(src.#names, dest.#names);)*
+            }
+
+            unsafe fn swap_one_nonoverlapping(ptr1: #ptr_name, ptr2: #ptr_name) {
+                #(#field_layouts::swap_one_nonoverlapping(ptr1.#names, ptr2.#names);)*
+            }
+
+            unsafe fn copy_leftwards(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #(#field_layouts::copy_leftwards(src.#names, dest.#names, count);)*
+            }
+
+            unsafe fn copy_rightwards(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #(#field_layouts::copy_rightwards(src.#names, dest.#names, count);)*
+            }
+
+            unsafe fn copy_nonoverlapping(src: #ptr_name, dest: #ptr_name, count: usize) {
+                #(#field_layouts::copy_nonoverlapping(src.#names, dest.#names, count);)*
+            }
+
+            unsafe fn swap_nonoverlapping(ptr1: #ptr_name, ptr2: #ptr_name, count: usize) {
+                #(#field_layouts::swap_nonoverlapping(ptr1.#names, ptr2.#names, count);)*
+            }
+        }
+
+        // The struct itself never exists in memory, so only `Copy` structs can
+        // be lent out as a temporary. The higher-ranked bound keeps this from
+        // being a hard error for structs that are not `Copy`.
+        unsafe impl ::arranged::layouts::Inspect<#name> for #layout_name where for<'x> #name: ::core::marker::Copy {
+            unsafe fn inspect<R, F: FnOnce(&#name) -> R>(ptr: #ptr_name, func: F) -> R {
+                func(&<Self as ::arranged::layouts::ArrayLayout<#name>>::read(ptr))
+            }
+        }
+
+        #vis struct #columns_name<'a> {
+            #(pub #names: ::arranged::Ref<'a, [#types], ::arranged::layouts::Slice<#layouts>>),*
+        }
+
+        #vis struct #columns_mut_name<'a> {
+            #(pub #names: ::arranged::RefMut<'a, [#types], ::arranged::layouts::Slice<#layouts>>),*
+        }
+
+        #vis trait #slice_trait<'a> {
+            fn unzip(self) -> #columns_name<'a>;
+            #(fn #names(self) -> ::arranged::Ref<'a, [#types], ::arranged::layouts::Slice<#layouts>>;)*
+        }
+
+        #vis trait #slice_mut_trait<'a> {
+            fn unzip(self) -> #columns_mut_name<'a>;
+            #(fn #names(self) -> ::arranged::RefMut<'a, [#types], ::arranged::layouts::Slice<#layouts>>;)*
+        }
+
+        impl<'a> #slice_trait<'a> for ::arranged::Ref<'a, [#name], ::arranged::layouts::Slice<#layout_name>> {
+            fn unzip(self) -> #columns_name<'a> {
+                let ptr = self.as_ptr();
+                let len = self.len();
+                unsafe { #columns_name { #(
+                    #names: ::arranged::Ref::from_raw(::arranged::layouts::slice::SlicePtr::from_raw_parts(ptr.#names, len))
+                ),* } }
+            }
+
+            #(
+                fn #names(self) -> ::arranged::Ref<'a, [#types], ::arranged::layouts::Slice<#layouts>> {
+                    unsafe {
+                        ::arranged::Ref::from_raw(::arranged::layouts::slice::SlicePtr::from_raw_parts(self.as_ptr().#names, self.len()))
+                    }
+                }
+            )*
+        }
+
+        impl<'a> #slice_mut_trait<'a> for ::arranged::RefMut<'a, [#name], ::arranged::layouts::Slice<#layout_name>> {
+            fn unzip(self) -> #columns_mut_name<'a> {
+                let ptr = self.as_ptr();
+                let len = self.len();
+                unsafe { #columns_mut_name { #(
+                    #names: ::arranged::RefMut::from_raw(::arranged::layouts::slice::SlicePtr::from_raw_parts(ptr.#names, len))
+                ),* } }
+            }
+
+            #(
+                fn #names(self) -> ::arranged::RefMut<'a, [#types], ::arranged::layouts::Slice<#layouts>> {
+                    unsafe {
+                        ::arranged::RefMut::from_raw(::arranged::layouts::slice::SlicePtr::from_raw_parts(self.as_ptr().#names, self.len()))
+                    }
+                }
+            )*
+        }
+    })
+}
@@ -1,20 +1,26 @@
-#![feature(allocator_api, dropck_eyepatch)]
+#![cfg_attr(feature = "nightly", feature(allocator_api, dropck_eyepatch))]
 #![no_std]
-extern crate alloc;
+#![allow(clippy::missing_safety_doc, clippy::redundant_field_names, clippy::type_complexity)]
+extern crate allocator_api2;
 extern crate arranged;
 
-use alloc::alloc::Global;
-use core::alloc::Alloc;
-use core::cmp;
+use allocator_api2::alloc::{Allocator, Global};
+use core::cmp::{self, Ordering};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{FromIterator, FusedIterator};
 use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 use core::ops::{RangeBounds, Bound};
+use core::ptr;
 
 use arranged::{Ref, RefMut};
-use arranged::layouts::{Flat, Slice};
-use arranged::layouts::ArrayLayout;
+use arranged::layouts::{Flat, PackedBits, Slice};
+use arranged::layouts::{ArrayLayout, Inspect};
 use arranged::layouts::slice::{SlicePtr, SliceIter, SliceIterMut};
 
-pub struct AVec<T, TLayout = Flat, A = Global> where TLayout: ArrayLayout<T>, A: Alloc {
+pub struct AVec<T, TLayout = Flat, A = Global> where TLayout: ArrayLayout<T>, A: Allocator {
     ptr: TLayout::Ptr,
     count: usize,
     capacity: usize,
@@ -22,12 +28,22 @@ pub struct AVec<T, TLayout = Flat, A = Global> where TLayout: ArrayLayout<T>, A:
     _marker: PhantomData<T>
 }
 
-unsafe impl<#[may_dangle] T, TLayout: ArrayLayout<T>, A: Alloc> Drop for AVec<T, TLayout, A> {
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, TLayout: ArrayLayout<T>, A: Allocator> Drop for AVec<T, TLayout, A> {
     fn drop(&mut self) {
         self.clear();
-        let (layout, array_info) = TLayout::layout_array(self.capacity);
         unsafe {
-            self.allocator.dealloc(TLayout::base_ptr(self.ptr, array_info), layout);
+            self.dealloc_buffer();
+        }
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> Drop for AVec<T, TLayout, A> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe {
+            self.dealloc_buffer();
         }
     }
 }
@@ -38,7 +54,7 @@ pub struct Drain<'a, T: 'a, TLayout> where TLayout: ArrayLayout<T> {
     full_range_start: TLayout::Ptr,
     full_range_end: TLayout::Ptr,
     range_start: TLayout::Ptr,
-    range_end: TLayout::Ptr,
+    range_count: usize,
     _marker: PhantomData<&'a mut [T]>
 }
 
@@ -46,10 +62,7 @@ impl<'a, T, TLayout: ArrayLayout<T>> Drop for Drain<'a, T, TLayout> {
     fn drop(&mut self) {
         unsafe {
             // Drop the remaining elements
-            while !TLayout::same_ptr(self.range_start, self.range_end) {
-                TLayout::drop_in_place(self.range_start);
-                self.range_start = TLayout::offset(self.range_start, 1);
-            }
+            Slice::<TLayout>::drop_in_place(SlicePtr::from_raw_parts(self.range_start, self.range_count));
 
             // Shift the remaining elements into place
             TLayout::copy_leftwards(self.full_range_end, self.full_range_start, self.shift_count);
@@ -63,32 +76,40 @@ impl<'a, T, TLayout: ArrayLayout<T>> Drop for Drain<'a, T, TLayout> {
 impl<'a, T, TLayout: ArrayLayout<T>> Iterator for Drain<'a, T, TLayout> {
     type Item = T;
     fn next(&mut self) -> Option<T> {
-        if unsafe { TLayout::same_ptr(self.range_start, self.range_end) } {
+        if self.range_count == 0 {
             None
         } else {
             unsafe {
                 let ret = TLayout::read(self.range_start);
                 self.range_start = TLayout::offset(self.range_start, 1);
+                self.range_count -= 1;
                 Some(ret)
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.range_count, Some(self.range_count))
+    }
 }
 
 impl<'a, T, TLayout: ArrayLayout<T>> DoubleEndedIterator for Drain<'a, T, TLayout> {
     fn next_back(&mut self) -> Option<T> {
-        if unsafe { TLayout::same_ptr(self.range_start, self.range_end) } {
+        if self.range_count == 0 {
             None
         } else {
             unsafe {
-                self.range_end = TLayout::offset(self.range_end, -1);
-                let ret = TLayout::read(self.range_end);
+                self.range_count -= 1;
+                let ret = TLayout::read(TLayout::offset(self.range_start, self.range_count as isize));
                 Some(ret)
             }
         }
     }
 }
 
+impl<'a, T, TLayout: ArrayLayout<T>> ExactSizeIterator for Drain<'a, T, TLayout> { }
+impl<'a, T, TLayout: ArrayLayout<T>> FusedIterator for Drain<'a, T, TLayout> { }
+
 pub struct DrainFilter<'a, F, T: 'a, TLayout> where TLayout: ArrayLayout<T>, F: FnMut(RefMut<T, TLayout>) -> bool {
     vec_count: &'a mut usize,
     base_ptr: TLayout::Ptr,
@@ -134,37 +155,115 @@ impl<'a, F: FnMut(RefMut<T, TLayout>) -> bool, T, TLayout: ArrayLayout<T>> Drop
     }
 }
 
+pub struct IntoIter<T, TLayout = Flat, A = Global> where TLayout: ArrayLayout<T>, A: Allocator {
+    buffer: TLayout::Ptr,
+    capacity: usize,
+    start: TLayout::Ptr,
+    count: usize,
+    allocator: A,
+    _marker: PhantomData<T>
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> Drop for IntoIter<T, TLayout, A> {
+    fn drop(&mut self) {
+        unsafe {
+            Slice::<TLayout>::drop_in_place(SlicePtr::from_raw_parts(self.start, self.count));
+            if self.capacity != 0 {
+                let (layout, array_info) = TLayout::layout_array(self.capacity);
+                self.allocator.deallocate(TLayout::base_ptr(self.buffer, array_info), layout);
+            }
+        }
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> Iterator for IntoIter<T, TLayout, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            unsafe {
+                let ret = TLayout::read(self.start);
+                self.start = TLayout::offset(self.start, 1);
+                self.count -= 1;
+                Some(ret)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.count, Some(self.count))
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> DoubleEndedIterator for IntoIter<T, TLayout, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.count == 0 {
+            None
+        } else {
+            unsafe {
+                self.count -= 1;
+                Some(TLayout::read(TLayout::offset(self.start, self.count as isize)))
+            }
+        }
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> ExactSizeIterator for IntoIter<T, TLayout, A> { }
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> FusedIterator for IntoIter<T, TLayout, A> { }
+
 impl<T, TLayout: ArrayLayout<T>> AVec<T, TLayout, Global> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> AVec<T, TLayout, A> {
+    pub fn new_in(allocator: A) -> Self {
         AVec {
             ptr: TLayout::dangling(),
             count: 0,
             capacity: 0,
-            allocator: Global,
+            allocator: allocator,
             _marker: PhantomData
         }
     }
 
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub fn with_capacity_in(capacity: usize, allocator: A) -> Self {
         let (layout, info) = TLayout::layout_array(capacity);
         if layout.size() == 0 {
-            return Self::new();
+            return Self::new_in(allocator);
         }
 
-        let allocation = unsafe { Global.alloc(layout).unwrap() };
+        let allocation = allocator.allocate(layout).unwrap().cast();
         let ptr = unsafe { TLayout::from_flat_ptr(allocation, info) };
         unsafe { TLayout::initialize(ptr, capacity); }
         AVec {
             ptr: ptr,
             count: 0,
             capacity: capacity,
-            allocator: Global,
+            allocator: allocator,
             _marker: PhantomData
         }
     }
-}
 
-impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    // Frees the current buffer without dropping any elements in it.
+    unsafe fn dealloc_buffer(&mut self) {
+        if self.capacity != 0 {
+            let (layout, array_info) = TLayout::layout_array(self.capacity);
+            self.allocator.deallocate(TLayout::base_ptr(self.ptr, array_info), layout);
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -232,7 +331,7 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
                 full_range_start: start_ptr,
                 full_range_end: end_ptr,
                 range_start: start_ptr,
-                range_end: end_ptr,
+                range_count: end - start,
                 _marker: PhantomData
             }
         }
@@ -308,6 +407,7 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
     }
 
     pub fn push(&mut self, value: T) {
+        assert!(TLayout::can_store(&value), "Value cannot be stored in this layout");
         self.reserve_one();
 
         unsafe {
@@ -324,17 +424,15 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
                 self.capacity * 2
             };
             let (new_layout, new_info) = TLayout::layout_array(new_capacity);
-            let new_allocation = unsafe { self.allocator.alloc(new_layout).unwrap() };
+            let new_allocation = self.allocator.allocate(new_layout).unwrap().cast();
             let new_ptr = unsafe { TLayout::from_flat_ptr(new_allocation, new_info) };
             unsafe { TLayout::initialize(new_ptr, new_capacity); }
 
-            if self.count != 0 {
-                unsafe {
+            unsafe {
+                if self.count != 0 {
                     TLayout::copy_nonoverlapping(self.ptr, new_ptr, self.count);
-
-                    let (old_layout, old_info) = TLayout::layout_array(self.capacity);
-                    self.allocator.dealloc(TLayout::base_ptr(self.ptr, old_info), old_layout);
                 }
+                self.dealloc_buffer();
             }
 
             self.ptr = new_ptr;
@@ -342,6 +440,16 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
         }
     }
 
+    // Like `push`, but hands back a value the layout cannot store (see
+    // `ArrayLayout::can_store`) instead of panicking.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if !TLayout::can_store(&value) {
+            return Err(value);
+        }
+        self.push(value);
+        Ok(())
+    }
+
     pub fn capacity(&self) -> usize {
         self.capacity
     }
@@ -350,17 +458,15 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
         if self.count + additional > self.capacity {
             let new_capacity = cmp::max(self.count + additional, self.capacity * 2);
             let (new_layout, new_info) = TLayout::layout_array(new_capacity);
-            let new_allocation = unsafe { self.allocator.alloc(new_layout).unwrap() };
+            let new_allocation = self.allocator.allocate(new_layout).unwrap().cast();
             let new_ptr = unsafe { TLayout::from_flat_ptr(new_allocation, new_info) };
             unsafe { TLayout::initialize(new_ptr, new_capacity); }
 
-            if self.count != 0 {
-                unsafe {
+            unsafe {
+                if self.count != 0 {
                     TLayout::copy_nonoverlapping(self.ptr, new_ptr, self.count);
-
-                    let (old_layout, old_info) = TLayout::layout_array(self.capacity);
-                    self.allocator.dealloc(TLayout::base_ptr(self.ptr, old_info), old_layout);
                 }
+                self.dealloc_buffer();
             }
 
             self.ptr = new_ptr;
@@ -368,7 +474,7 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
         }
     }
 
-    pub fn append<OtherA: Alloc>(&mut self, other: &mut AVec<T, TLayout, OtherA>) {
+    pub fn append<OtherA: Allocator>(&mut self, other: &mut AVec<T, TLayout, OtherA>) {
         self.reserve(other.count);
         unsafe {
             TLayout::copy_nonoverlapping(other.ptr, TLayout::offset(self.ptr, self.count as isize), other.count);
@@ -378,6 +484,7 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
 
     pub fn insert(&mut self, index: usize, value: T) {
         assert!(index <= self.len());
+        assert!(TLayout::can_store(&value), "Value cannot be stored in this layout");
         self.reserve_one();
         unsafe {
             TLayout::copy_rightwards(TLayout::offset(self.ptr, index as isize), TLayout::offset(self.ptr, (index + 1) as isize), self.len() - index);
@@ -386,15 +493,210 @@ impl<T, TLayout: ArrayLayout<T>, A: Alloc> AVec<T, TLayout, A> {
         }
     }
 
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        if !TLayout::can_store(&value) {
+            return Err(value);
+        }
+        self.insert(index, value);
+        Ok(())
+    }
+
     pub fn retain<F: for<'a> FnMut(Ref<'a, T, TLayout>) -> bool>(&mut self, mut func: F) {
         self.drain_filter(move |x| !func(x.reborrow()));
     }
 }
 
+impl<T, TLayout: ArrayLayout<T>, A: Allocator + Default> Default for AVec<T, TLayout, A> {
+    fn default() -> Self {
+        AVec::new_in(A::default())
+    }
+}
+
+impl<T: Clone, TLayout: Inspect<T>, A: Allocator + Clone> Clone for AVec<T, TLayout, A> {
+    fn clone(&self) -> Self {
+        let mut vec = AVec::with_capacity_in(self.len(), self.allocator.clone());
+        for elem in self.iter() {
+            vec.push(elem.inspect(T::clone));
+        }
+        vec
+    }
+}
+
+impl<T: fmt::Debug, TLayout: Inspect<T>, A: Allocator> fmt::Debug for AVec<T, TLayout, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T, U, TLayout, ULayout, A, B> PartialEq<AVec<U, ULayout, B>> for AVec<T, TLayout, A>
+        where T: PartialEq<U>, TLayout: Inspect<T>, ULayout: Inspect<U>, A: Allocator, B: Allocator {
+    fn eq(&self, other: &AVec<U, ULayout, B>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, TLayout: Inspect<T>, A: Allocator> Eq for AVec<T, TLayout, A> { }
+
+impl<T, U, TLayout, ULayout, A, B> PartialOrd<AVec<U, ULayout, B>> for AVec<T, TLayout, A>
+        where T: PartialOrd<U>, TLayout: Inspect<T>, ULayout: Inspect<U>, A: Allocator, B: Allocator {
+    fn partial_cmp(&self, other: &AVec<U, ULayout, B>) -> Option<Ordering> {
+        self.as_slice().partial_cmp(&other.as_slice())
+    }
+}
+
+impl<T: Ord, TLayout: Inspect<T>, A: Allocator> Ord for AVec<T, TLayout, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(&other.as_slice())
+    }
+}
+
+impl<T: Hash, TLayout: Inspect<T>, A: Allocator> Hash for AVec<T, TLayout, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> IntoIterator for AVec<T, TLayout, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, TLayout, A>;
+
+    fn into_iter(self) -> IntoIter<T, TLayout, A> {
+        // The buffer and allocator now belong to the iterator
+        let vec = ManuallyDrop::new(self);
+        IntoIter {
+            buffer: vec.ptr,
+            capacity: vec.capacity,
+            start: vec.ptr,
+            count: vec.count,
+            allocator: unsafe { ptr::read(&vec.allocator) },
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<'a, T: 'a, TLayout: ArrayLayout<T>, A: Allocator> IntoIterator for &'a AVec<T, TLayout, A> {
+    type Item = Ref<'a, T, TLayout>;
+    type IntoIter = SliceIter<'a, T, TLayout>;
+
+    fn into_iter(self) -> SliceIter<'a, T, TLayout> {
+        self.iter()
+    }
+}
+
+impl<'a, T: 'a, TLayout: ArrayLayout<T>, A: Allocator> IntoIterator for &'a mut AVec<T, TLayout, A> {
+    type Item = RefMut<'a, T, TLayout>;
+    type IntoIter = SliceIterMut<'a, T, TLayout>;
+
+    fn into_iter(self) -> SliceIterMut<'a, T, TLayout> {
+        self.iter_mut()
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>> FromIterator<T> for AVec<T, TLayout, Global> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = AVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T, TLayout: ArrayLayout<T>, A: Allocator> Extend<T> for AVec<T, TLayout, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, TLayout: ArrayLayout<T>, OtherLayout: ArrayLayout<T>, A: Allocator> Extend<Ref<'a, T, OtherLayout>> for AVec<T, TLayout, A> {
+    fn extend<I: IntoIterator<Item = Ref<'a, T, OtherLayout>>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|elem| elem.get()));
+    }
+}
+
+// The operators on bit vectors defer to those on packed slices, which panic
+// if the lengths differ.
+macro_rules! bit_op_impl {
+    ($Op:ident $op:ident $OpAssign:ident $op_assign:ident) => {
+        impl<'b, WordLayout, OtherLayout, A, B> $OpAssign<&'b AVec<bool, PackedBits<OtherLayout>, B>> for AVec<bool, PackedBits<WordLayout>, A>
+                where WordLayout: ArrayLayout<usize>, OtherLayout: ArrayLayout<usize>, A: Allocator, B: Allocator {
+            fn $op_assign(&mut self, other: &'b AVec<bool, PackedBits<OtherLayout>, B>) {
+                let mut slice = self.as_mut_slice();
+                slice.$op_assign(other.as_slice());
+            }
+        }
+
+        impl<'b, WordLayout, OtherLayout, A, B> $Op<&'b AVec<bool, PackedBits<OtherLayout>, B>> for AVec<bool, PackedBits<WordLayout>, A>
+                where WordLayout: ArrayLayout<usize>, OtherLayout: ArrayLayout<usize>, A: Allocator, B: Allocator {
+            type Output = Self;
+
+            fn $op(mut self, other: &'b AVec<bool, PackedBits<OtherLayout>, B>) -> Self {
+                self.$op_assign(other);
+                self
+            }
+        }
+
+        impl<'a, 'b, WordLayout, OtherLayout, A, B> $Op<&'b AVec<bool, PackedBits<OtherLayout>, B>> for &'a AVec<bool, PackedBits<WordLayout>, A>
+                where WordLayout: ArrayLayout<usize>, OtherLayout: ArrayLayout<usize>, A: Allocator + Clone, B: Allocator {
+            type Output = AVec<bool, PackedBits<WordLayout>, A>;
+
+            fn $op(self, other: &'b AVec<bool, PackedBits<OtherLayout>, B>) -> AVec<bool, PackedBits<WordLayout>, A> {
+                self.clone().$op(other)
+            }
+        }
+    }
+}
+
+bit_op_impl!(BitAnd bitand BitAndAssign bitand_assign);
+bit_op_impl!(BitOr bitor BitOrAssign bitor_assign);
+bit_op_impl!(BitXor bitxor BitXorAssign bitxor_assign);
+
+impl<WordLayout: ArrayLayout<usize>, A: Allocator> Not for AVec<bool, PackedBits<WordLayout>, A> {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        self.as_mut_slice().not_assign();
+        self
+    }
+}
+
+impl<WordLayout: ArrayLayout<usize>, A: Allocator + Clone> Not for &AVec<bool, PackedBits<WordLayout>, A> {
+    type Output = AVec<bool, PackedBits<WordLayout>, A>;
+
+    fn not(self) -> AVec<bool, PackedBits<WordLayout>, A> {
+        !self.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
+    use self::std::format;
+    use self::std::panic::{self, AssertUnwindSafe};
+    use self::std::vec::Vec;
+    use self::std::rc::Rc;
+    use self::std::vec;
+    use core::alloc::Layout;
+    use core::cell::Cell;
+    use core::cmp::{self, Ordering};
+    use core::ptr::NonNull;
     use super::AVec;
-    use arranged::layouts::{Flat, Parallel, PackedBits};
+    use arranged::{ArrayLayout, Ref, RefMut, Slice};
+    use arranged::layouts::{Aligned, ArrayOfLanes, BigEndian, Chunked, Fallible, Flat, LittleEndian, Nullable, Parallel, PackedBits, PackedInts, Strided, Unaligned};
+    use arranged::layouts::ArrayLayout as ArrayLayoutTrait;
+    use arranged::layouts::Inspect;
+    use arranged::layouts::bitvec::RankDirectory;
+
+    #[derive(ArrayLayout, Clone, Copy, Debug, PartialEq)]
+    struct Particle {
+        pos: (i32, i32),
+        mass: u64,
+        #[arranged(layout = PackedBits<Flat>)]
+        alive: bool
+    }
 
     #[test]
     fn push_pop() {
@@ -424,6 +726,49 @@ mod tests {
         assert_eq!(vec.pop(), None);
     }
 
+    #[test]
+    fn parallel_triple() {
+        let mut vec: AVec<(u8, u64, bool), Parallel<(Flat, Flat, PackedBits<Flat>)>> = AVec::new();
+        for i in 0..100 {
+            vec.push((i as u8, i * 3, i % 2 == 0));
+        }
+        vec.remove(10);
+        vec.insert(0, (200, 7, true));
+
+        let (bytes, words, _) = vec.as_slice().unzip();
+        assert_eq!(*bytes.into_iter().next().unwrap(), 200);
+        assert_eq!(words.into_iter().map(|w| *w).sum::<u64>(), 7 + (0..100).sum::<u64>() * 3 - 30);
+
+        for i in (11..100).rev() {
+            assert_eq!(vec.pop(), Some((i as u8, i * 3, i % 2 == 0)));
+        }
+    }
+
+    #[test]
+    fn split_elements() {
+        let mut vec: AVec<(u32, u16), Parallel<(Flat, Flat)>> = AVec::new();
+        let mut flat: AVec<(u32, u16)> = AVec::new();
+        for i in 0..20 {
+            vec.push((i, i as u16 + 1));
+            flat.push((i, i as u16 + 1));
+        }
+
+        for elem in vec.iter_mut() {
+            let (left, mut right) = elem.split();
+            *right += *left as u16;
+        }
+        for elem in flat.as_mut_slice().strided() {
+            let (left, mut right) = elem.split();
+            *right += *left as u16;
+        }
+        for (elem, strided) in vec.iter().zip(flat.as_slice().strided()) {
+            let (left, right) = elem.split();
+            assert_eq!(*right, *left as u16 * 2 + 1);
+            let (strided_left, strided_right) = strided.split();
+            assert_eq!((*strided_left, *strided_right), (*left, *right));
+        }
+    }
+
     #[test]
     fn bitvec() {
         let mut vec: AVec<bool, PackedBits<Flat>> = AVec::new();
@@ -448,4 +793,788 @@ mod tests {
         }
         assert_eq!(vec.pop(), None);
     }
+
+    #[test]
+    fn derived_columns() {
+        let mut vec: AVec<Particle, ParticleLayout> = AVec::new();
+        for i in 0..10 {
+            vec.push(Particle { pos: (i, -i), mass: i as u64 * 10, alive: i % 3 == 0 });
+        }
+
+        for (i, pos) in vec.as_slice().pos().into_iter().enumerate() {
+            assert_eq!(*pos, (i as i32, -(i as i32)));
+        }
+        let columns = vec.as_mut_slice().unzip();
+        for mut mass in columns.mass {
+            *mass += 1;
+        }
+        assert_eq!(vec.clone(), vec);
+        assert_eq!(vec.as_slice().first().unwrap().inspect(|particle| particle.mass), 1);
+
+        for i in (0..10).rev() {
+            assert_eq!(vec.pop(), Some(Particle { pos: (i, -i), mass: i as u64 * 10 + 1, alive: i % 3 == 0 }));
+        }
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn get_set_bits() {
+        let mut vec: AVec<bool, PackedBits<Flat>> = AVec::new();
+        for i in 0..130 {
+            vec.push(i % 5 == 0);
+        }
+
+        for (i, mut bit) in vec.iter_mut().enumerate() {
+            let value = bit.get();
+            bit.set(!value);
+            if i == 64 {
+                assert!(RefMut::take(bit));
+            }
+        }
+        for (i, bit) in vec.iter().enumerate() {
+            assert_eq!(bit.get(), i % 5 != 0 && i != 64);
+        }
+
+        let mut iter = vec.iter_mut();
+        let first = iter.next().unwrap();
+        let second = iter.next().unwrap();
+        RefMut::swap(first, second);
+        assert_eq!(vec.iter().next().map(|bit| bit.get()), Some(true));
+    }
+
+    #[test]
+    fn slice_indexing() {
+        let mut vec: AVec<bool, PackedBits<Flat>> = AVec::new();
+        for i in 0..200 {
+            vec.push(i % 7 == 0);
+        }
+
+        let slice = vec.as_slice();
+        assert_eq!(slice.get(63).map(|bit| bit.get()), Some(true));
+        assert!(slice.get(200).is_none());
+        assert!(slice.get_range(150..201).is_none());
+        assert_eq!(slice.last().map(|bit| bit.get()), Some(false));
+
+        let (left, right) = slice.slice(1..=140).split_at(69);
+        assert_eq!((left.len(), right.len()), (69, 71));
+        assert_eq!(right.first().map(|bit| bit.get()), Some(true));
+        let (last, rest) = left.split_last().unwrap();
+        assert!(!last.get());
+        assert_eq!(rest.into_iter().filter(|bit| bit.get()).count(), 9);
+
+        let (left, right) = vec.as_mut_slice().split_at_mut(100);
+        let (mut first, _) = right.split_first_mut().unwrap();
+        first.set(true);
+        left.last_mut().unwrap().set(true);
+        assert_eq!(vec.as_slice().slice(99..101).into_iter().filter(|bit| bit.get()).count(), 2);
+    }
+
+    #[test]
+    fn iterator_traits() {
+        let mut vec: AVec<(u16, bool), Parallel<(Flat, PackedBits<Flat>)>> = AVec::new();
+        for i in 0..100 {
+            vec.push((i, i % 4 == 0));
+        }
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.len(), 100);
+        assert_eq!(iter.nth(10).map(|elem| elem.get()), Some((10, false)));
+        assert_eq!(iter.nth_back(11).map(|elem| elem.get()), Some((88, true)));
+        assert_eq!(iter.len(), 77);
+        assert_eq!(iter.rev().map(|elem| elem.get().0).next(), Some(87));
+        assert!(vec.iter().nth(100).is_none());
+
+        let doubled: AVec<u16> = vec.iter().zip(vec.iter().rev()).fold(AVec::new(), |mut acc, (front, back)| {
+            acc.push(front.get().0 + back.get().0);
+            acc
+        });
+        assert!(doubled.iter().all(|sum| *sum == 99));
+
+        let mut drain = vec.drain(20..30);
+        assert_eq!(drain.len(), 10);
+        assert_eq!(drain.next_back(), Some((29, false)));
+        assert_eq!(drain.size_hint(), (9, Some(9)));
+        drop(drain);
+        assert_eq!(vec.len(), 90);
+        assert_eq!(vec.iter().nth(20).map(|elem| elem.get()), Some((30, false)));
+    }
+
+    #[test]
+    fn standard_traits() {
+        let mut packed: AVec<(u8, bool), Parallel<(Flat, PackedBits<Flat>)>> = AVec::default();
+        let mut flat: AVec<(u8, bool)> = AVec::new();
+        for i in 0..5 {
+            packed.push((i, i % 2 == 0));
+            flat.push((i, i % 2 == 0));
+        }
+
+        let cloned = packed.clone();
+        assert_eq!(cloned, flat);
+        assert_eq!(cloned, packed);
+        flat.push((0, false));
+        assert!(packed < flat);
+        assert_eq!(packed.cmp(&cloned), Ordering::Equal);
+
+        packed.truncate(2);
+        assert_eq!(format!("{:?}", packed), "[(0, true), (1, false)]");
+    }
+
+    #[test]
+    fn collect_and_into_iter() {
+        let bits: AVec<bool, PackedBits<Flat>> = (0..100).map(|i| i % 3 == 0).collect();
+        let mut copied: AVec<bool> = AVec::new();
+        copied.extend(bits.as_slice().slice(50..));
+        copied.extend(&bits);
+        assert_eq!(copied.len(), 150);
+        assert!(copied.into_iter().rev().take(100).eq((0..100).rev().map(|i| i % 3 == 0)));
+
+        struct DropCounter<'a>(&'a Cell<usize>);
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let vec: AVec<(DropCounter, u8), Parallel<(Flat, Flat)>> = (0..10).map(|i| (DropCounter(&drops), i)).collect();
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(iter.next_back().map(|(_, i)| i), Some(9));
+        assert_eq!(iter.next().map(|(_, i)| i), Some(0));
+        assert_eq!(drops.get(), 2);
+        drop(iter);
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[test]
+    fn sorting() {
+        let mut seed = 12345u32;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 50
+        };
+        let original: AVec<(u32, u32)> = (0..300).map(|i| (random(), i)).collect();
+        let mut expected: std::vec::Vec<(u32, u32)> = original.iter().map(|elem| *elem).collect();
+        expected.sort_by_key(|elem| elem.0);
+
+        let mut parallel: AVec<(u32, u32), Parallel<(Flat, Flat)>> = original.iter().map(|elem| *elem).collect();
+        parallel.as_mut_slice().sort_by_key(|elem| elem.split().0.get());
+        assert!(parallel.iter().map(|elem| elem.get()).eq(expected.iter().cloned()));
+
+        let mut parallel: AVec<(u32, u32), Parallel<(Flat, Flat)>> = original.iter().map(|elem| *elem).collect();
+        parallel.as_mut_slice().sort_columns_by_key(|elem| elem.split().0.get());
+        assert!(parallel.iter().map(|elem| elem.get()).eq(expected.iter().cloned()));
+
+        let mut flat = original.clone();
+        flat.as_mut_slice().sort_by_cached_key(|elem| elem.0);
+        assert!(flat.iter().map(|elem| *elem).eq(expected.iter().cloned()));
+
+        let mut flat = original.clone();
+        flat.as_mut_slice().sort_unstable();
+        expected.sort();
+        assert!(flat.iter().map(|elem| *elem).eq(expected.iter().cloned()));
+
+        let mut bits: AVec<bool, PackedBits<Flat>> = (0..150).map(|i| i % 3 == 1).collect();
+        bits.as_mut_slice().sort_unstable_by(|a, b| b.cmp(&a));
+        assert!(bits.iter().take(50).all(|bit| bit.get()));
+        assert!(!bits.iter().skip(50).any(|bit| bit.get()));
+    }
+
+    #[test]
+    fn searching() {
+        let mut vec: AVec<(u32, bool), Parallel<(Flat, PackedBits<Flat>)>> = (0..100).map(|i| (i / 3 * 2, i % 2 == 0)).collect();
+        let key = |elem: Ref<(u32, bool), Parallel<(Flat, PackedBits<Flat>)>>| elem.split().0.get();
+
+        assert_eq!(vec.as_slice().binary_search_by_key(&10, key).map(|i| i / 3), Ok(5));
+        assert_eq!(vec.as_slice().equal_range_by_key(&10, key), 15..18);
+        assert_eq!(vec.as_slice().equal_range_by_key(&11, key), 18..18);
+        assert_eq!(vec.as_slice().partition_point(|elem| key(elem) < 40), 60);
+
+        let index = vec.as_slice().binary_search_by_key(&11, key).unwrap_err();
+        vec.insert(index, (11, true));
+        assert_eq!(vec.as_slice().binary_search(&(11, true)), Ok(18));
+        assert_eq!(vec.as_slice().binary_search(&(200, false)), Err(101));
+
+        let bits: AVec<bool, PackedBits<Flat>> = (0..200).map(|i| i >= 77).collect();
+        assert_eq!(bits.as_slice().partition_point(|bit| !bit.get()), 77);
+        assert_eq!(bits.as_slice().equal_range(&true), 77..200);
+    }
+
+    #[test]
+    fn slice_mutators() {
+        let pattern = |i: usize| i.is_multiple_of(5) || i.is_multiple_of(7);
+        let mut bits: AVec<bool, PackedBits<Flat>> = (0..150).map(pattern).collect();
+        let mut model: std::vec::Vec<bool> = (0..150).map(pattern).collect();
+
+        bits.as_mut_slice().reverse();
+        model.reverse();
+        bits.as_mut_slice().rotate_left(67);
+        model.rotate_left(67);
+        bits.as_mut_slice().slice_mut(3..140).rotate_right(100);
+        model[3..140].rotate_right(100);
+        bits.as_mut_slice().copy_within(10..80, 60);
+        model.copy_within(10..80, 60);
+        bits.as_mut_slice().copy_within(90..140, 1);
+        model.copy_within(90..140, 1);
+        bits.as_mut_slice().swap_indices(0, 149);
+        model.swap(0, 149);
+        assert!(bits.iter().map(|bit| bit.get()).eq(model.iter().cloned()));
+
+        let mut flat: AVec<bool> = (0..150).map(|_| false).collect();
+        flat.as_mut_slice().copy_from_other_layout(bits.as_slice());
+        assert_eq!(flat, bits);
+        let mut copy: AVec<bool, PackedBits<Flat>> = (0..140).map(|_| false).collect();
+        copy.as_mut_slice().copy_from_slice(bits.as_slice().slice(5..145));
+        assert!(copy.iter().eq(bits.as_slice().slice(5..145)));
+
+        bits.as_mut_slice().slice_mut(..100).fill(true);
+        let mut count = 0;
+        bits.as_mut_slice().slice_mut(100..).fill_with(|| { count += 1; count % 2 == 0 });
+        assert_eq!(bits.iter().filter(|bit| bit.get()).count(), 125);
+    }
+
+    // `PackedBits` without its bulk overrides, so that it falls back on the
+    // per-element default implementations.
+    struct DefaultBits;
+
+    unsafe impl ArrayLayoutTrait<bool> for DefaultBits {
+        type Ptr = <PackedBits<Flat> as ArrayLayoutTrait<bool>>::Ptr;
+        type ArrayInfo = ();
+
+        fn layout_array(count: usize) -> (Layout, ()) {
+            PackedBits::<Flat>::layout_array(count)
+        }
+        unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: ()) -> Self::Ptr {
+            PackedBits::<Flat>::from_flat_ptr(ptr, info)
+        }
+        unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+            PackedBits::<Flat>::initialize(ptr, count)
+        }
+        unsafe fn base_ptr(ptr: Self::Ptr, info: ()) -> NonNull<u8> {
+            PackedBits::<Flat>::base_ptr(ptr, info)
+        }
+        fn dangling() -> Self::Ptr {
+            PackedBits::<Flat>::dangling()
+        }
+        unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+            PackedBits::<Flat>::offset(ptr, offset)
+        }
+        unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+            PackedBits::<Flat>::same_ptr(ptr1, ptr2)
+        }
+        unsafe fn read(ptr: Self::Ptr) -> bool {
+            PackedBits::<Flat>::read(ptr)
+        }
+        unsafe fn write(ptr: Self::Ptr, value: bool) {
+            PackedBits::<Flat>::write(ptr, value)
+        }
+        unsafe fn drop_in_place(_ptr: Self::Ptr) { }
+    }
+
+    unsafe impl Inspect<bool> for DefaultBits {
+        unsafe fn inspect<R, F: FnOnce(&bool) -> R>(ptr: Self::Ptr, func: F) -> R {
+            func(&Self::read(ptr))
+        }
+    }
+
+    #[test]
+    fn packed_bits_bulk_ops_match_defaults() {
+        const LEN: usize = 400;
+        let mut seed = 987654321u64;
+        let mut random = move |bound: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 33) as usize) % bound
+        };
+
+        for round in 0..3000 {
+            let mut fast: AVec<bool, PackedBits<Flat>> = AVec::new();
+            let mut slow: AVec<bool, DefaultBits> = AVec::new();
+            for _ in 0..LEN {
+                let bit = random(2) == 0;
+                fast.push(bit);
+                slow.push(bit);
+            }
+
+            let count = random(LEN / 2);
+            let (a, b) = match round % 4 {
+                // Overlapping moves towards the front
+                0 => { let dest = random(LEN - count); (dest + random(LEN - count - dest + 1), dest) },
+                // Overlapping moves towards the back
+                1 => { let src = random(LEN - count); (src, src + random(LEN - count - src + 1)) },
+                // Disjoint ranges in either order
+                _ => {
+                    let first = random(LEN - 2 * count + 1);
+                    let second = first + count + random(LEN - 2 * count - first + 1);
+                    if random(2) == 0 { (first, second) } else { (second, first) }
+                }
+            };
+
+            unsafe {
+                let fast_base = fast.as_mut_slice().as_ptr();
+                let slow_base = slow.as_mut_slice().as_ptr();
+                let fast_a = PackedBits::<Flat>::offset(fast_base, a as isize);
+                let fast_b = PackedBits::<Flat>::offset(fast_base, b as isize);
+                let slow_a = DefaultBits::offset(slow_base, a as isize);
+                let slow_b = DefaultBits::offset(slow_base, b as isize);
+                match round % 4 {
+                    0 => {
+                        PackedBits::<Flat>::copy_leftwards(fast_a, fast_b, count);
+                        DefaultBits::copy_leftwards(slow_a, slow_b, count);
+                    },
+                    1 => {
+                        PackedBits::<Flat>::copy_rightwards(fast_a, fast_b, count);
+                        DefaultBits::copy_rightwards(slow_a, slow_b, count);
+                    },
+                    2 => {
+                        PackedBits::<Flat>::copy_nonoverlapping(fast_a, fast_b, count);
+                        DefaultBits::copy_nonoverlapping(slow_a, slow_b, count);
+                    },
+                    _ => {
+                        PackedBits::<Flat>::swap_nonoverlapping(fast_a, fast_b, count);
+                        DefaultBits::swap_nonoverlapping(slow_a, slow_b, count);
+                    }
+                }
+            }
+            assert_eq!(fast, slow, "round {} with a = {}, b = {}, count = {}", round, a, b, count);
+        }
+    }
+
+    #[test]
+    fn rank_select() {
+        let mut seed = 12345u64;
+        let bools: Vec<bool> = (0..500).map(|i| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            // Leave a long empty stretch to exercise the skipping of zero words
+            !(100..300).contains(&i) && (seed >> 60) < 5
+        }).collect();
+        let bits: AVec<bool, PackedBits<Flat>> = bools.iter().cloned().collect();
+        let ones: Vec<usize> = (0..bools.len()).filter(|&i| bools[i]).collect();
+
+        let slice = bits.as_slice();
+        assert_eq!(slice.count_ones(), ones.len());
+        assert_eq!(slice.count_zeros(), bools.len() - ones.len());
+        assert_eq!(slice.ones().collect::<Vec<_>>(), ones);
+        assert_eq!(slice.first_one(), ones.first().cloned());
+        assert_eq!(slice.last_one(), ones.last().cloned());
+        assert_eq!(slice.slice(100..300).first_one(), None);
+        assert_eq!(slice.slice(3..).ones().next(), ones.iter().find(|&&i| i >= 3).map(|&i| i - 3));
+
+        let directory = RankDirectory::new(slice);
+        assert_eq!(directory.count_ones(), ones.len());
+        for index in 0..bools.len() + 1 {
+            let expected = ones.iter().filter(|&&i| i < index).count();
+            assert_eq!(slice.rank1(index), expected);
+            assert_eq!(directory.rank1(index), expected);
+            assert_eq!(directory.rank0(index), index - expected);
+        }
+        for n in 0..ones.len() + 2 {
+            assert_eq!(slice.select1(n), ones.get(n).cloned());
+            assert_eq!(directory.select1(n), ones.get(n).cloned());
+        }
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        let mut seed = 24680u64;
+        let mut random_bits = |len: usize| -> Vec<bool> {
+            (0..len).map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (seed >> 63) != 0
+            }).collect()
+        };
+        let left = random_bits(200);
+        let right = random_bits(200);
+        let left_bits: AVec<bool, PackedBits<Flat>> = left.iter().cloned().collect();
+        let right_bits: AVec<bool, PackedBits<Flat>> = right.iter().cloned().collect();
+
+        let expect = |op: fn(bool, bool) -> bool| -> AVec<bool, PackedBits<Flat>> {
+            left.iter().zip(&right).map(|(&a, &b)| op(a, b)).collect()
+        };
+        assert_eq!(&left_bits & &right_bits, expect(|a, b| a & b));
+        assert_eq!(&left_bits | &right_bits, expect(|a, b| a | b));
+        assert_eq!(&left_bits ^ &right_bits, expect(|a, b| a ^ b));
+        assert_eq!(!&left_bits, expect(|a, _| !a));
+
+        // Differently aligned subslices, leaving the bits outside them alone
+        for &(dest_start, src_start, len) in &[(3, 70, 100), (70, 3, 100), (0, 64, 64), (5, 5, 190), (130, 17, 1)] {
+            let mut bits = left_bits.clone();
+            {
+                let mut dest = bits.as_mut_slice().slice_mut(dest_start..dest_start + len);
+                dest ^= right_bits.as_slice().slice(src_start..src_start + len);
+                dest.not_assign();
+            }
+            for i in 0..left.len() {
+                let expected = if i >= dest_start && i < dest_start + len {
+                    !(left[i] ^ right[i - dest_start + src_start])
+                } else {
+                    left[i]
+                };
+                assert_eq!(bits.as_slice().get(i).map(|bit| bit.get()), Some(expected), "bit {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn packed_ints() {
+        let mut ids: AVec<u16, PackedInts<12, Flat>> = AVec::new();
+        let mut expected = Vec::new();
+        for i in 0..300u16 {
+            let id = i.wrapping_mul(2654) % 4096;
+            ids.push(id);
+            expected.push(id);
+        }
+        ids.insert(7, 4095);
+        expected.insert(7, 4095);
+        assert_eq!(ids.remove(100), expected.remove(100));
+        ids.as_mut_slice().rotate_left(13);
+        expected.rotate_left(13);
+        ids.as_mut_slice().sort();
+        expected.sort();
+        assert_eq!(ids.iter().map(|id| id.get()).collect::<Vec<_>>(), expected);
+
+        let tags: AVec<u8, PackedInts<3, Flat>> = (0..100).map(|i| i % 8).collect();
+        assert_eq!(tags.iter().map(|tag| tag.get()).collect::<Vec<_>>(), (0..100).map(|i| i % 8).collect::<Vec<_>>());
+
+        let wide: AVec<u64, PackedInts<64, Flat>> = vec![0, !0, 1 << 63, 12345].into_iter().collect();
+        assert_eq!(wide.iter().map(|value| value.get()).collect::<Vec<_>>(), vec![0, !0, 1 << 63, 12345]);
+    }
+
+    #[test]
+    fn packed_ints_out_of_range() {
+        let mut tags: AVec<u8, PackedInts<3, Flat>> = (0..4).collect();
+        assert_eq!(tags.try_push(8), Err(8));
+        assert_eq!(tags.try_insert(1, 9), Err(9));
+        assert_eq!(tags.as_mut_slice().get_mut(2).unwrap().try_set(200), Err(200));
+        assert_eq!(tags.iter().map(|tag| tag.get()).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        assert_eq!(tags.try_insert(1, 7), Ok(()));
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| tags.insert(1, 9))).is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| tags.as_mut_slice().fill(8))).is_err());
+        assert_eq!(tags.iter().map(|tag| tag.get()).collect::<Vec<_>>(), vec![0, 7, 1, 2, 3]);
+
+        let mut pairs: AVec<(u32, u8), Parallel<(Flat, PackedInts<3, Flat>)>> = AVec::new();
+        assert_eq!(pairs.try_push((1, 7)), Ok(()));
+        assert_eq!(pairs.try_push((2, 8)), Err((2, 8)));
+        assert_eq!(pairs.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn packed_ints_push_out_of_range() {
+        let mut tags: AVec<u8, PackedInts<3, Flat>> = AVec::new();
+        tags.push(8);
+    }
+
+    #[test]
+    fn nullable() {
+        struct DropCounter<'a>(&'a Cell<usize>, u32);
+        impl<'a> Drop for DropCounter<'a> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        {
+            let mut vec: AVec<Option<DropCounter>, Nullable<Flat>> = (0..100u32).map(|i| {
+                if i % 3 == 0 { None } else { Some(DropCounter(&drops, i)) }
+            }).collect();
+            assert_eq!(vec.as_slice().null_count(), 34);
+            assert!(vec.as_slice().get(0).unwrap().is_none());
+            assert_eq!(vec.as_slice().get(4).unwrap().as_option().map(|value| value.1), Some(4));
+
+            vec.insert(1, None);
+            vec.insert(0, Some(DropCounter(&drops, 1000)));
+            vec.remove(51);
+            vec.as_mut_slice().reverse();
+            vec.as_mut_slice().rotate_left(7);
+            assert_eq!(drops.get(), 1);
+            assert_eq!(vec.as_slice().null_count(), 35);
+            if let Some(mut value) = vec.as_mut_slice().first_mut().and_then(|first| first.as_option_mut()) {
+                value.1 += 1;
+            }
+
+            let values: Vec<Option<u32>> = vec.iter().map(|elem| elem.as_option().map(|value| value.1)).collect();
+            let mut expected: Vec<Option<u32>> = (0..100u32).map(|i| if i % 3 == 0 { None } else { Some(i) }).collect();
+            expected.insert(1, None);
+            expected.insert(0, Some(1000));
+            expected.remove(51);
+            expected.reverse();
+            expected.rotate_left(7);
+            if let Some(value) = expected[0].as_mut() {
+                *value += 1;
+            }
+            assert_eq!(values, expected);
+        }
+        // Each of the 67 `Some` values exactly once, never an empty slot
+        assert_eq!(drops.get(), 67);
+    }
+
+    #[test]
+    fn nullable_moves_onto_itself() {
+        let mut vec: AVec<Option<u32>, Nullable<Flat>> = (0..10).map(|i| if i % 3 == 0 { None } else { Some(i) }).collect();
+        assert_eq!(vec.drain(3..3).count(), 0);
+        vec.as_mut_slice().copy_within(2..5, 2);
+        let values: Vec<Option<u32>> = vec.iter().map(|elem| elem.get()).collect();
+        assert_eq!(values, (0..10).map(|i| if i % 3 == 0 { None } else { Some(i) }).collect::<Vec<_>>());
+    }
+
+    #[derive(ArrayLayout, Debug, PartialEq)]
+    enum Shape {
+        Circle { radius: f32 },
+        Rect(f32, f32),
+        Empty,
+        Label(Rc<u32>)
+    }
+
+    #[test]
+    fn enum_columns() {
+        let label = Rc::new(7);
+        let mut shapes: AVec<Shape, ShapeLayout> = AVec::new();
+        for i in 0..20 {
+            shapes.push(match i % 4 {
+                0 => Shape::Circle { radius: i as f32 },
+                1 => Shape::Rect(i as f32, 2.0),
+                2 => Shape::Empty,
+                _ => Shape::Label(label.clone())
+            });
+        }
+        assert_eq!(Rc::strong_count(&label), 6);
+        assert_eq!(shapes.as_slice().get(5).unwrap().tag(), ShapeLayout::RECT);
+        assert_eq!(shapes.as_slice().tags().into_iter().filter(|&tag| tag.get() == ShapeLayout::LABEL).count(), 5);
+
+        shapes.remove(3);
+        shapes.insert(0, Shape::Empty);
+        shapes.as_mut_slice().reverse();
+        assert_eq!(Rc::strong_count(&label), 5);
+        assert_eq!(shapes.pop(), Some(Shape::Empty));
+        assert_eq!(shapes.pop(), Some(Shape::Circle { radius: 0.0 }));
+        assert_eq!(shapes.as_slice().first().map(|shape| shape.tag()), Some(ShapeLayout::LABEL));
+
+        let radii: Vec<f32> = shapes.iter().filter_map(|shape| shape.as_circle()).map(|circle| circle.radius.get()).collect();
+        assert_eq!(radii, vec![16.0, 12.0, 8.0, 4.0]);
+        for shape in shapes.iter_mut() {
+            if let Some(mut rect) = shape.as_rect() {
+                *rect.1 += *rect.0;
+            }
+        }
+        let rects: Vec<(f32, f32)> = shapes.iter().filter_map(|shape| shape.as_rect()).map(|rect| (*rect.0, *rect.1)).collect();
+        assert_eq!(rects, vec![(17.0, 19.0), (13.0, 15.0), (9.0, 11.0), (5.0, 7.0), (1.0, 3.0)]);
+        assert!(shapes.as_slice().first().unwrap().as_circle().is_none());
+        assert_eq!(shapes.as_slice().first().unwrap().as_label().map(|label| **label.0), Some(7));
+
+        drop(shapes);
+        assert_eq!(Rc::strong_count(&label), 1);
+    }
+
+    #[test]
+    fn fallible() {
+        let error = Rc::new(());
+        let mut results: AVec<Result<u64, Rc<()>>, Fallible<Flat, Flat>> = (0..200u64).map(|i| {
+            if i % 37 == 5 { Err(error.clone()) } else { Ok(i) }
+        }).collect();
+        assert_eq!(results.as_slice().err_count(), 6);
+        assert_eq!(results.as_slice().ok_count(), 194);
+        assert!(results.as_slice().get(5).unwrap().is_err());
+        assert_eq!(results.as_slice().get(6).unwrap().as_result().map(|value| value.get()).ok(), Some(6));
+
+        assert_eq!(results.remove(0).ok(), Some(0));
+        results.insert(100, Err(error.clone()));
+        results.as_mut_slice().swap_indices(0, 4);
+        results.as_mut_slice().rotate_right(3);
+        assert_eq!(Rc::strong_count(&error), 8);
+
+        let oks: Vec<u64> = results.as_slice().oks().map(|value| value.get()).collect();
+        let mut expected: Vec<Result<u64, ()>> = (0..200u64).map(|i| if i % 37 == 5 { Err(()) } else { Ok(i) }).collect();
+        assert_eq!(expected.remove(0), Ok(0));
+        expected.insert(100, Err(()));
+        expected.swap(0, 4);
+        expected.rotate_right(3);
+        assert_eq!(oks, expected.iter().filter_map(|result| result.ok()).collect::<Vec<_>>());
+        assert!(results.iter().map(|result| result.is_ok()).eq(expected.iter().map(|result| result.is_ok())));
+
+        drop(results);
+        assert_eq!(Rc::strong_count(&error), 1);
+    }
+
+    #[test]
+    fn fallible_moves_onto_itself() {
+        let expected: Vec<Result<u32, u8>> = (0..10).map(|i| if i % 4 == 1 { Err(i as u8) } else { Ok(i) }).collect();
+        let mut results: AVec<Result<u32, u8>, Fallible<Flat, Flat>> = expected.iter().cloned().collect();
+        assert_eq!(results.drain(3..3).count(), 0);
+        results.as_mut_slice().copy_within(2..5, 2);
+        assert_eq!(results.iter().map(|result| result.get()).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn array_lanes() {
+        let mut points: AVec<[f32; 3], ArrayOfLanes<3, Flat>> = (0..10).map(|i| [i as f32, 10.0 + i as f32, 20.0 + i as f32]).collect();
+        points.remove(2);
+        points.insert(0, [-1.0, -2.0, -3.0]);
+        assert_eq!(points.as_slice().get(1).map(|point| point.get()), Some([0.0, 10.0, 20.0]));
+
+        let [xs, ys, zs] = points.as_slice().lanes();
+        assert_eq!(xs.get(3).map(|x| x.get()), Some(3.0));
+        assert_eq!(ys.last().map(|y| y.get()), Some(19.0));
+        assert_eq!(zs.first().map(|z| z.get()), Some(-3.0));
+        assert!(points.as_slice().lane(1).into_iter().map(|y| y.get()).eq(points.iter().map(|point| point.get()[1])));
+
+        for y in points.as_mut_slice().lane(1) {
+            RefMut::replace(y, 0.0);
+        }
+        let [x, y, z] = points.as_slice().get(4).unwrap().split();
+        assert_eq!((x.get(), y.get(), z.get()), (4.0, 0.0, 24.0));
+    }
+
+    #[test]
+    fn chunked() {
+        let mut vec: AVec<(u32, u8), Chunked<4, Parallel<(Flat, Flat)>>> = (0..30).map(|i| (i, i as u8 * 2)).collect();
+        let mut expected: Vec<(u32, u8)> = (0..30).map(|i| (i, i as u8 * 2)).collect();
+        assert_eq!(vec.remove(5), expected.remove(5));
+        vec.insert(2, (100, 1));
+        expected.insert(2, (100, 1));
+        vec.drain(9..17);
+        expected.drain(9..17);
+        vec.as_mut_slice().slice_mut(1..).rotate_left(3);
+        expected[1..].rotate_left(3);
+        vec.as_mut_slice().sort_by_key(|elem| elem.get().1 % 5);
+        expected.sort_by_key(|elem| elem.1 % 5);
+        assert_eq!(vec.iter().map(|elem| elem.get()).collect::<Vec<_>>(), expected);
+
+        let mut chunks = vec.as_slice().chunks_exact();
+        assert_eq!(chunks.len(), 5);
+        for (index, chunk) in chunks.by_ref().enumerate() {
+            let (ids, _) = chunk.unzip();
+            assert!(ids.into_iter().map(|id| id.get()).eq(expected[index * 4..][..4].iter().map(|elem| elem.0)));
+        }
+        assert_eq!(chunks.remainder().len(), 2);
+
+        for chunk in vec.as_mut_slice().chunks_exact_mut() {
+            let (_, mut flags) = chunk.unzip();
+            flags.fill(0);
+        }
+        assert_eq!(vec.iter().filter(|elem| elem.get().1 == 0).count(), 20 + expected[20..].iter().filter(|elem| elem.1 == 0).count());
+
+        // Slices starting mid-block yield the elements up to the boundary first
+        let ids: Vec<u32> = vec.iter().map(|elem| elem.get().0).collect();
+        for &(start, end) in &[(1, 22), (3, 5), (2, 2), (4, 22)] {
+            let mut chunks = vec.as_slice().slice(start..end).chunks_exact();
+            let head_len = if start % 4 == 0 { 0 } else { cmp::min(end - start, 4 - start % 4) };
+            assert!(chunks.head().into_iter().map(|elem| elem.get().0).eq(ids[start..start + head_len].iter().cloned()));
+            let mut next = start + head_len;
+            for chunk in chunks.by_ref() {
+                let (chunk_ids, _) = chunk.unzip();
+                assert!(chunk_ids.into_iter().map(|id| id.get()).eq(ids[next..next + 4].iter().cloned()));
+                next += 4;
+            }
+            assert!(chunks.remainder().into_iter().map(|elem| elem.get().0).eq(ids[next..end].iter().cloned()));
+        }
+        let mut chunks = vec.as_mut_slice().slice_mut(3..).chunks_exact_mut();
+        chunks.take_head().fill((7, 7));
+        assert_eq!(chunks.take_head().len(), 0);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(vec.as_slice().get(3).unwrap().get(), (7, 7));
+    }
+
+    #[test]
+    fn aligned_columns() {
+        // 13 floats round up to 16, then to a whole 64-byte line
+        let (layout, ()) = <Aligned<64, Flat, 16> as ArrayLayoutTrait<f32>>::layout_array(13);
+        assert_eq!((layout.size(), layout.align()), (64, 64));
+        let (layout, ()) = <Aligned<32, Flat> as ArrayLayoutTrait<u8>>::layout_array(40);
+        assert_eq!((layout.size(), layout.align()), (64, 32));
+
+        let mut vec: AVec<(u8, f32), Parallel<(Flat, Aligned<64, Flat, 8>)>> = AVec::new();
+        assert_eq!(vec.as_slice().unzip().1.as_ptr().as_ptr() as usize % 64, 0);
+        for i in 0..50 {
+            vec.push((i as u8, i as f32));
+            let (_, floats) = vec.as_slice().unzip();
+            assert_eq!(floats.as_ptr().as_ptr() as usize % 64, 0);
+        }
+        let (bytes, mut floats) = vec.as_mut_slice().unzip();
+        assert_eq!(bytes.len(), 50);
+        floats.reborrow_mut().as_mut_slice()[3] = -1.0;
+        assert_eq!(&floats.reborrow().as_slice()[..5], &[0.0, 1.0, 2.0, -1.0, 4.0]);
+    }
+
+    #[test]
+    fn unaligned_records() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C)]
+        struct Header {
+            kind: u16,
+            length: u16,
+            sequence: u32
+        }
+
+        // A leading byte throws every record off its natural alignment
+        let mut packet = [0u8; 1 + 3 * 8 + 5];
+        for (i, byte) in packet.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let headers = unsafe { Ref::<[Header], Slice<Unaligned>>::from_bytes(&packet[1..]) };
+        assert_eq!(headers.len(), 3);
+        let second = headers.get(1).unwrap().get();
+        assert_eq!(second.kind, u16::from_ne_bytes([9, 10]));
+        assert_eq!(second.sequence, u32::from_ne_bytes([13, 14, 15, 16]));
+
+        {
+            let mut headers = unsafe { RefMut::<[Header], Slice<Unaligned>>::from_bytes(&mut packet[1..]) };
+            headers.swap_indices(0, 2);
+            headers.reborrow_mut().get_mut(1).unwrap().set(Header { kind: 1, length: 2, sequence: 3 });
+        }
+        assert_eq!(&packet[1..9], &[17, 18, 19, 20, 21, 22, 23, 24]);
+        assert_eq!(&packet[9..11], &1u16.to_ne_bytes());
+        assert_eq!(&packet[25..], &[25, 26, 27, 28, 29]);
+
+        let mut owned: AVec<Header, Unaligned> = AVec::new();
+        owned.extend(unsafe { Ref::<[Header], Slice<Unaligned>>::from_bytes(&packet[1..]) });
+        owned.insert(0, second);
+        assert_eq!(owned.as_slice().get(2).map(|header| header.get()), Some(Header { kind: 1, length: 2, sequence: 3 }));
+    }
+
+    #[test]
+    fn byte_order() {
+        let mut file = [0u8, 0, 0, 1, 0, 0, 1, 0, 0x3f, 0x80, 0, 0, 0xff];
+        let big = Ref::<[u32], Slice<BigEndian<Unaligned>>>::from_bytes(&file);
+        assert_eq!(big.into_iter().map(|value| value.get()).collect::<Vec<_>>(), vec![1, 256, 0x3f800000]);
+        let little = Ref::<[u32], Slice<LittleEndian<Unaligned>>>::from_bytes(&file);
+        assert_eq!(little.get(1).map(|value| value.get()), Some(0x00010000));
+        let floats = Ref::<[f32], Slice<BigEndian<Unaligned>>>::from_bytes(&file[8..]);
+        assert_eq!(floats.get(0).map(|value| value.get()), Some(1.0));
+
+        {
+            let mut shorts = RefMut::<[i16], Slice<BigEndian<Unaligned>>>::from_bytes(&mut file[..4]);
+            shorts.reborrow_mut().get_mut(1).unwrap().set(-2);
+            shorts.swap_indices(0, 1);
+        }
+        assert_eq!(&file[..4], &[0xff, 0xfe, 0, 0]);
+
+        let mut vec: AVec<(u32, f64), Parallel<(BigEndian<Strided>, LittleEndian<Flat>)>> = (0..20).map(|i| (i, i as f64 / 2.0)).collect();
+        vec.remove(3);
+        vec.insert(0, (0xdeadbeef, -0.0));
+        vec.as_mut_slice().rotate_left(5);
+        assert_eq!(vec.as_slice().get(15).map(|elem| elem.get()), Some((0xdeadbeef, -0.0)));
+        assert_eq!(vec.as_slice().get(0).map(|elem| elem.get()), Some((5, 2.5)));
+        // Reading the column without the conversion shows the stored bytes
+        let stored = unsafe { Ref::<u32, Strided>::from_raw(vec.as_slice().get(15).unwrap().as_raw().0).get() };
+        assert_eq!(stored.to_ne_bytes(), [0xde, 0xad, 0xbe, 0xef]);
+    }
+
+    #[test]
+    fn guard_writes_back() {
+        let mut vec: AVec<(u32, bool), Parallel<(Flat, PackedBits<Flat>)>> = AVec::new();
+        for i in 0..70 {
+            vec.push((i, false));
+        }
+
+        for elem in vec.iter_mut() {
+            let mut guard = RefMut::guard(elem);
+            guard.0 += 1;
+            guard.1 = guard.0.is_multiple_of(2);
+        }
+        for (i, elem) in vec.iter().enumerate() {
+            assert_eq!(elem.get(), (i as u32 + 1, !i.is_multiple_of(2)));
+        }
+    }
 }
@@ -0,0 +1,124 @@
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use core::slice;
+
+use reference::{Ref, RefMut};
+use layouts::flat::Flat;
+use layouts::slice::Slice;
+use layouts::{ArrayLayout, Inspect};
+
+// Raises the alignment of the array laid out by `Inner` to `ALIGN` bytes,
+// which also aligns it as a column when composed with `Parallel`. The count
+// is rounded up to a multiple of `PAD` elements, and the size to a multiple of
+// `ALIGN`, so that whole vectors can be loaded past the last element without
+// leaving the allocation. The padding elements are never initialized.
+pub struct Aligned<const ALIGN: usize, Inner, const PAD: usize = 1> {
+    _marker: PhantomData<Inner>
+}
+
+impl<const ALIGN: usize, Inner, const PAD: usize> Aligned<ALIGN, Inner, PAD> {
+    const VALID: () = assert!(ALIGN.is_power_of_two() && PAD > 0, "Aligned needs a power of two alignment and a nonzero padding");
+
+    fn padded_count(count: usize) -> usize {
+        count.checked_next_multiple_of(PAD).expect("Overflow in calculating array layout")
+    }
+}
+
+unsafe impl<const ALIGN: usize, T, Inner: ArrayLayout<T>, const PAD: usize> ArrayLayout<T> for Aligned<ALIGN, Inner, PAD> {
+    type Ptr = Inner::Ptr;
+    type ArrayInfo = Inner::ArrayInfo;
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let () = Self::VALID;
+        let (layout, info) = Inner::layout_array(Self::padded_count(count));
+        (layout.align_to(ALIGN).expect("Overflow in calculating array layout").pad_to_align(), info)
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        Inner::from_flat_ptr(ptr, info)
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        Inner::initialize(ptr, Self::padded_count(count));
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+        Inner::base_ptr(ptr, info)
+    }
+
+    // `Inner::dangling` is only aligned for `T`, so lay out an empty array at
+    // the first address with the raised alignment instead.
+    fn dangling() -> Self::Ptr {
+        let () = Self::VALID;
+        let (layout, info) = Inner::layout_array(0);
+        let align = layout.align_to(ALIGN).expect("Overflow in calculating array layout").align();
+        unsafe { Inner::from_flat_ptr(NonNull::new_unchecked(align as *mut u8), info) }
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        Inner::offset(ptr, offset)
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        Inner::same_ptr(ptr1, ptr2)
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> T {
+        Inner::read(ptr)
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: T) {
+        Inner::write(ptr, value);
+    }
+
+    unsafe fn drop_in_place(ptr: Self::Ptr) {
+        Inner::drop_in_place(ptr);
+    }
+
+    fn can_store(value: &T) -> bool {
+        Inner::can_store(value)
+    }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        Inner::copy_one_nonoverlapping(src, dest);
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+        Inner::swap_one_nonoverlapping(ptr1, ptr2);
+    }
+
+    unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        Inner::copy_leftwards(src, dest, count);
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        Inner::copy_rightwards(src, dest, count);
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        Inner::copy_nonoverlapping(src, dest, count);
+    }
+
+    unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+        Inner::swap_nonoverlapping(ptr1, ptr2, count);
+    }
+}
+
+unsafe impl<const ALIGN: usize, T, Inner: Inspect<T>, const PAD: usize> Inspect<T> for Aligned<ALIGN, Inner, PAD> {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: Self::Ptr, func: F) -> R {
+        Inner::inspect(ptr, func)
+    }
+}
+
+impl<'a, const ALIGN: usize, T, const PAD: usize> Ref<'a, [T], Slice<Aligned<ALIGN, Flat, PAD>>> {
+    pub fn as_slice(self) -> &'a [T] {
+        unsafe { slice::from_raw_parts(self.as_ptr().as_ptr(), self.len()) }
+    }
+}
+
+impl<'a, const ALIGN: usize, T, const PAD: usize> RefMut<'a, [T], Slice<Aligned<ALIGN, Flat, PAD>>> {
+    pub fn as_mut_slice(self) -> &'a mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_ptr().as_ptr(), self.len()) }
+    }
+}
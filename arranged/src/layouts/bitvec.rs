@@ -1,11 +1,17 @@
 use core::alloc::Layout;
+use core::cmp;
+use core::iter::FusedIterator;
 use core::marker::PhantomData;
-use core::mem::size_of;
+use core::ops::{BitAndAssign, BitOrAssign, BitXorAssign};
 use core::ptr::NonNull;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
+use layouts::slice::Slice;
+use reference::{Ref, RefMut};
 
-const USIZE_BITS: usize = size_of::<usize>() * 8;
+pub(crate) const USIZE_BITS: usize = usize::BITS as usize;
 
 pub struct PackedBits<WordLayout> {
     _marker: PhantomData<WordLayout>
@@ -21,12 +27,46 @@ impl<WordLayout: ArrayLayout<usize>> Clone for BitPtr<WordLayout> {
     fn clone(&self) -> Self { *self }
 }
 
+fn low_mask(len: usize) -> usize {
+    if len == USIZE_BITS {
+        !0
+    } else {
+        (1 << len) - 1
+    }
+}
+
+// Reads `len` bits, where `1 <= len <= USIZE_BITS`, into the low bits of the
+// result. Only touches the second word if the bits actually straddle it.
+pub(crate) unsafe fn read_bits<WordLayout: ArrayLayout<usize>>(ptr: BitPtr<WordLayout>, len: usize) -> usize {
+    let bit_index = ptr.bit_index as usize;
+    let mut value = WordLayout::read(ptr.word_ptr) >> bit_index;
+    if bit_index + len > USIZE_BITS {
+        value |= WordLayout::read(WordLayout::offset(ptr.word_ptr, 1)) << (USIZE_BITS - bit_index);
+    }
+    value & low_mask(len)
+}
+
+// Writes the low `len` bits of `value`, leaving all surrounding bits intact.
+pub(crate) unsafe fn write_bits<WordLayout: ArrayLayout<usize>>(ptr: BitPtr<WordLayout>, len: usize, value: usize) {
+    let bit_index = ptr.bit_index as usize;
+    let first_len = cmp::min(len, USIZE_BITS - bit_index);
+    let mask = low_mask(first_len) << bit_index;
+    let old_word = WordLayout::read(ptr.word_ptr);
+    WordLayout::write(ptr.word_ptr, (old_word & !mask) | ((value << bit_index) & mask));
+    if first_len < len {
+        let next_ptr = WordLayout::offset(ptr.word_ptr, 1);
+        let mask = low_mask(len - first_len);
+        let old_word = WordLayout::read(next_ptr);
+        WordLayout::write(next_ptr, (old_word & !mask) | ((value >> first_len) & mask));
+    }
+}
+
 unsafe impl<WordLayout: ArrayLayout<usize>> ArrayLayout<bool> for PackedBits<WordLayout> {
     type Ptr = BitPtr<WordLayout>;
     type ArrayInfo = WordLayout::ArrayInfo;
 
     fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
-        let word_count = (count + USIZE_BITS - 1) / USIZE_BITS;
+        let word_count = count.div_ceil(USIZE_BITS);
         WordLayout::layout_array(word_count)
     }
 
@@ -50,7 +90,7 @@ unsafe impl<WordLayout: ArrayLayout<usize>> ArrayLayout<bool> for PackedBits<Wor
         // memory behind a feature flag.
         #[cfg(not(feature = "uninit_packedbits"))]
         {
-            let word_count = (count + USIZE_BITS - 1) / USIZE_BITS;
+            let word_count = count.div_ceil(USIZE_BITS);
             let word_end = WordLayout::offset(ptr.word_ptr, word_count as isize);
             let mut cur_word = ptr.word_ptr;
             while !WordLayout::same_ptr(cur_word, word_end) {
@@ -101,6 +141,313 @@ unsafe impl<WordLayout: ArrayLayout<usize>> ArrayLayout<bool> for PackedBits<Wor
 
     unsafe fn drop_in_place(_ptr: Self::Ptr) { }
 
-    // TODO: copy_nonoverlapping could be a lot more efficient
+    // The bulk operations below move a word at a time. They first move just
+    // enough bits to align the destination to a word boundary, so that the
+    // main loop can overwrite whole destination words without reading them.
+    // Chunks are processed in the same order as the per-element defaults, so
+    // the same overlap guarantees hold.
+
+    unsafe fn copy_leftwards(mut src: Self::Ptr, mut dest: Self::Ptr, count: usize) {
+        let mut remaining = count;
+        if dest.bit_index != 0 && remaining > 0 {
+            let head = cmp::min(remaining, USIZE_BITS - dest.bit_index as usize);
+            write_bits(dest, head, read_bits(src, head));
+            src = Self::offset(src, head as isize);
+            dest = Self::offset(dest, head as isize);
+            remaining -= head;
+        }
+        while remaining >= USIZE_BITS {
+            WordLayout::write(dest.word_ptr, read_bits(src, USIZE_BITS));
+            src.word_ptr = WordLayout::offset(src.word_ptr, 1);
+            dest.word_ptr = WordLayout::offset(dest.word_ptr, 1);
+            remaining -= USIZE_BITS;
+        }
+        if remaining > 0 {
+            write_bits(dest, remaining, read_bits(src, remaining));
+        }
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        let mut remaining = count;
+        let tail = cmp::min(remaining, Self::offset(dest, count as isize).bit_index as usize);
+        if tail > 0 {
+            remaining -= tail;
+            write_bits(Self::offset(dest, remaining as isize), tail, read_bits(Self::offset(src, remaining as isize), tail));
+        }
+        while remaining >= USIZE_BITS {
+            remaining -= USIZE_BITS;
+            let word = read_bits(Self::offset(src, remaining as isize), USIZE_BITS);
+            WordLayout::write(Self::offset(dest, remaining as isize).word_ptr, word);
+        }
+        if remaining > 0 {
+            write_bits(dest, remaining, read_bits(src, remaining));
+        }
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        Self::copy_leftwards(src, dest, count);
+    }
+
+    unsafe fn swap_nonoverlapping(mut ptr1: Self::Ptr, mut ptr2: Self::Ptr, count: usize) {
+        // The two ranges may still share a word at their boundary, so only
+        // the aligned words of `ptr1` are written without masking.
+        let mut remaining = count;
+        if ptr1.bit_index != 0 && remaining > 0 {
+            let head = cmp::min(remaining, USIZE_BITS - ptr1.bit_index as usize);
+            let bits1 = read_bits(ptr1, head);
+            let bits2 = read_bits(ptr2, head);
+            write_bits(ptr1, head, bits2);
+            write_bits(ptr2, head, bits1);
+            ptr1 = Self::offset(ptr1, head as isize);
+            ptr2 = Self::offset(ptr2, head as isize);
+            remaining -= head;
+        }
+        while remaining >= USIZE_BITS {
+            let word1 = WordLayout::read(ptr1.word_ptr);
+            let word2 = read_bits(ptr2, USIZE_BITS);
+            WordLayout::write(ptr1.word_ptr, word2);
+            write_bits(ptr2, USIZE_BITS, word1);
+            ptr1.word_ptr = WordLayout::offset(ptr1.word_ptr, 1);
+            ptr2.word_ptr = WordLayout::offset(ptr2.word_ptr, 1);
+            remaining -= USIZE_BITS;
+        }
+        if remaining > 0 {
+            let bits1 = read_bits(ptr1, remaining);
+            let bits2 = read_bits(ptr2, remaining);
+            write_bits(ptr1, remaining, bits2);
+            write_bits(ptr2, remaining, bits1);
+        }
+    }
+}
+
+unsafe impl<WordLayout: ArrayLayout<usize>> Inspect<bool> for PackedBits<WordLayout> {
+    unsafe fn inspect<R, F: FnOnce(&bool) -> R>(ptr: Self::Ptr, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
+
+// Reads the (possibly partial) word of the slice starting at bit `start`.
+unsafe fn read_chunk<WordLayout: ArrayLayout<usize>>(base: BitPtr<WordLayout>, len: usize, start: usize) -> usize {
+    read_bits(PackedBits::offset(base, start as isize), cmp::min(USIZE_BITS, len - start))
+}
+
+// Finds the position of the `n`th set bit of `word`, which must have more
+// than `n` bits set.
+fn select_in_word(mut word: usize, n: usize) -> usize {
+    for _ in 0..n {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl<'a, WordLayout: ArrayLayout<usize>> Ref<'a, [bool], Slice<PackedBits<WordLayout>>> {
+    pub fn count_ones(self) -> usize {
+        let len = self.len();
+        (0..len).step_by(USIZE_BITS).map(|start| unsafe {
+            read_chunk(self.as_ptr(), len, start).count_ones() as usize
+        }).sum()
+    }
+
+    pub fn count_zeros(self) -> usize {
+        self.len() - self.count_ones()
+    }
+
+    // The number of set bits strictly before `index`.
+    pub fn rank1(self, index: usize) -> usize {
+        assert!(index <= self.len(), "rank index {} out of range for slice of length {}", index, self.len());
+        self.slice(..index).count_ones()
+    }
+
+    pub fn rank0(self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    // The position of the `n`th set bit, counting from zero.
+    pub fn select1(self, mut n: usize) -> Option<usize> {
+        let len = self.len();
+        for start in (0..len).step_by(USIZE_BITS) {
+            let word = unsafe { read_chunk(self.as_ptr(), len, start) };
+            let ones = word.count_ones() as usize;
+            if n < ones {
+                return Some(start + select_in_word(word, n));
+            }
+            n -= ones;
+        }
+        None
+    }
+
+    pub fn first_one(self) -> Option<usize> {
+        self.ones().next()
+    }
+
+    pub fn last_one(self) -> Option<usize> {
+        let len = self.len();
+        let mut end = len;
+        while end > 0 {
+            let start = end.saturating_sub(USIZE_BITS);
+            let word = unsafe { read_bits(PackedBits::offset(self.as_ptr(), start as isize), end - start) };
+            if word != 0 {
+                return Some(start + (USIZE_BITS - 1 - word.leading_zeros() as usize));
+            }
+            end = start;
+        }
+        None
+    }
+
+    pub fn ones(self) -> Ones<'a, WordLayout> {
+        Ones {
+            bits: self,
+            word: 0,
+            word_start: 0,
+            next_start: 0
+        }
+    }
+}
+
+// Iterates over the positions of the set bits of a packed slice, in order.
+pub struct Ones<'a, WordLayout: ArrayLayout<usize>> {
+    bits: Ref<'a, [bool], Slice<PackedBits<WordLayout>>>,
+    // The not yet yielded bits of the chunk starting at `word_start`
+    word: usize,
+    word_start: usize,
+    next_start: usize
+}
+
+impl<'a, WordLayout: ArrayLayout<usize>> Iterator for Ones<'a, WordLayout> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let len = self.bits.len();
+        while self.word == 0 {
+            if self.next_start >= len {
+                return None;
+            }
+            self.word = unsafe { read_chunk(self.bits.as_ptr(), len, self.next_start) };
+            self.word_start = self.next_start;
+            self.next_start += USIZE_BITS;
+        }
+        let position = self.word_start + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(position)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let current = self.word.count_ones() as usize;
+        (current, Some(current + self.bits.len().saturating_sub(self.next_start)))
+    }
+}
+
+impl<'a, WordLayout: ArrayLayout<usize>> FusedIterator for Ones<'a, WordLayout> { }
+
+// A precomputed directory of cumulative popcounts, one per word, answering
+// `rank1` in constant time and `select1` in logarithmic time. It borrows the
+// bits immutably, so they cannot change while the directory is alive.
+#[cfg(feature = "alloc")]
+pub struct RankDirectory<'a, WordLayout: ArrayLayout<usize>> {
+    bits: Ref<'a, [bool], Slice<PackedBits<WordLayout>>>,
+    // `ranks[i]` counts the set bits before the `i`th chunk, with a final
+    // entry holding the total.
+    ranks: Vec<usize>
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, WordLayout: ArrayLayout<usize>> RankDirectory<'a, WordLayout> {
+    pub fn new(bits: Ref<'a, [bool], Slice<PackedBits<WordLayout>>>) -> Self {
+        let len = bits.len();
+        let mut ranks = Vec::with_capacity(len / USIZE_BITS + 2);
+        let mut total = 0;
+        ranks.push(0);
+        for start in (0..len).step_by(USIZE_BITS) {
+            total += unsafe { read_chunk(bits.as_ptr(), len, start).count_ones() as usize };
+            ranks.push(total);
+        }
+        RankDirectory {
+            bits: bits,
+            ranks: ranks
+        }
+    }
+
+    pub fn bits(&self) -> Ref<'a, [bool], Slice<PackedBits<WordLayout>>> {
+        self.bits
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ranks[self.ranks.len() - 1]
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.bits.len() - self.count_ones()
+    }
+
+    pub fn rank1(&self, index: usize) -> usize {
+        let len = self.bits.len();
+        assert!(index <= len, "rank index {} out of range for slice of length {}", index, len);
+        let chunk = index / USIZE_BITS;
+        let within = index % USIZE_BITS;
+        let mut rank = self.ranks[chunk];
+        if within > 0 {
+            let start = index - within;
+            rank += unsafe { read_bits(PackedBits::offset(self.bits.as_ptr(), start as isize), within).count_ones() as usize };
+        }
+        rank
+    }
+
+    pub fn rank0(&self, index: usize) -> usize {
+        index - self.rank1(index)
+    }
+
+    pub fn select1(&self, n: usize) -> Option<usize> {
+        if n >= self.count_ones() {
+            return None;
+        }
+        // The last chunk with fewer than `n + 1` ones before it
+        let chunk = self.ranks.partition_point(|&rank| rank <= n) - 1;
+        let start = chunk * USIZE_BITS;
+        let word = unsafe { read_chunk(self.bits.as_ptr(), self.bits.len(), start) };
+        Some(start + select_in_word(word, n - self.ranks[chunk]))
+    }
 }
 
+// Replaces each word of `dest` with `op` applied to it and the corresponding
+// bits of `src`. The two slices may start at different bit offsets; only the
+// bits of `dest` are ever written, so trailing bits in its last word survive.
+unsafe fn combine_bits<WordLayout, SrcLayout, F>(dest: BitPtr<WordLayout>, src: BitPtr<SrcLayout>, len: usize, op: F)
+        where WordLayout: ArrayLayout<usize>, SrcLayout: ArrayLayout<usize>, F: Fn(usize, usize) -> usize {
+    for start in (0..len).step_by(USIZE_BITS) {
+        let chunk_len = cmp::min(USIZE_BITS, len - start);
+        let dest_chunk = PackedBits::offset(dest, start as isize);
+        let src_chunk = PackedBits::offset(src, start as isize);
+        write_bits(dest_chunk, chunk_len, op(read_bits(dest_chunk, chunk_len), read_bits(src_chunk, chunk_len)));
+    }
+}
+
+// A mutable slice only supports the assigning operators, which modify it in
+// place. There is no by-value form: it would have to mutate the slice behind
+// the caller's back just to produce a result.
+macro_rules! bit_op_impl {
+    ($OpAssign:ident $op_assign:ident $operator:tt) => {
+        impl<'a, 'b, WordLayout, OtherLayout> $OpAssign<Ref<'b, [bool], Slice<PackedBits<OtherLayout>>>> for RefMut<'a, [bool], Slice<PackedBits<WordLayout>>>
+                where WordLayout: ArrayLayout<usize>, OtherLayout: ArrayLayout<usize> {
+            fn $op_assign(&mut self, other: Ref<'b, [bool], Slice<PackedBits<OtherLayout>>>) {
+                assert!(self.len() == other.len(), "bitwise operation on slices of lengths {} and {}", self.len(), other.len());
+                unsafe {
+                    combine_bits(self.as_ptr(), other.as_ptr(), self.len(), |a, b| a $operator b);
+                }
+            }
+        }
+    }
+}
+
+bit_op_impl!(BitAndAssign bitand_assign &);
+bit_op_impl!(BitOrAssign bitor_assign |);
+bit_op_impl!(BitXorAssign bitxor_assign ^);
+
+impl<'a, WordLayout: ArrayLayout<usize>> RefMut<'a, [bool], Slice<PackedBits<WordLayout>>> {
+    // Flips every bit of the slice in place.
+    pub fn not_assign(&mut self) {
+        let ptr = self.as_ptr();
+        unsafe {
+            combine_bits(ptr, ptr, self.len(), |a, _| !a);
+        }
+    }
+}
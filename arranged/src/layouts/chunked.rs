@@ -0,0 +1,269 @@
+use core::alloc::Layout;
+use core::cmp;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::slice::{Slice, SlicePtr};
+use layouts::{ArrayLayout, Inspect};
+
+// Groups elements into blocks of `W`, each laid out as a `W`-element array of
+// `Inner`, so that `Chunked<8, Parallel<..>>` keeps the columns of every 8
+// elements together.
+pub struct Chunked<const W: usize, Inner> {
+    _marker: PhantomData<Inner>
+}
+
+// Like `BitPtr`, this points at a block and a lane within that block. Since
+// the position of each column within a block is only known from the block's
+// `ArrayInfo`, that is carried along as well.
+pub struct ChunkedPtr<Info> {
+    block: NonNull<u8>,
+    lane: usize,
+    stride: usize,
+    info: Info
+}
+
+impl<Info: Copy> Copy for ChunkedPtr<Info> { }
+impl<Info: Copy> Clone for ChunkedPtr<Info> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<const W: usize, Inner> Chunked<W, Inner> {
+    const NONEMPTY: () = assert!(W > 0, "Chunked needs blocks of at least one element");
+
+    // The layout of a single block, padded so that blocks can be placed
+    // back to back.
+    fn block_layout<T>() -> (Layout, Inner::ArrayInfo) where Inner: ArrayLayout<T> {
+        let () = Self::NONEMPTY;
+        let (layout, info) = Inner::layout_array(W);
+        (layout.pad_to_align(), info)
+    }
+
+    unsafe fn block_ptr<T>(ptr: ChunkedPtr<Inner::ArrayInfo>) -> Inner::Ptr where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+        Inner::from_flat_ptr(ptr.block, ptr.info)
+    }
+
+    unsafe fn inner_ptr<T>(ptr: ChunkedPtr<Inner::ArrayInfo>) -> Inner::Ptr where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+        Inner::offset(Self::block_ptr(ptr), ptr.lane as isize)
+    }
+}
+
+unsafe impl<const W: usize, T, Inner> ArrayLayout<T> for Chunked<W, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+    type Ptr = ChunkedPtr<Inner::ArrayInfo>;
+    type ArrayInfo = (Inner::ArrayInfo, usize);
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let (block_layout, info) = Self::block_layout();
+        let size = block_layout.size().checked_mul(count.div_ceil(W)).expect("Overflow in calculating array layout");
+        let layout = Layout::from_size_align(size, block_layout.align()).expect("Overflow in calculating array layout");
+        (layout, (info, block_layout.size()))
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        ChunkedPtr {
+            block: ptr,
+            lane: 0,
+            stride: info.1,
+            info: info.0
+        }
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        debug_assert!(ptr.lane == 0);
+        let mut block = ptr;
+        for _ in 0..count.div_ceil(W) {
+            Inner::initialize(Self::block_ptr(block), W);
+            block.block = NonNull::new_unchecked(block.block.as_ptr().add(block.stride));
+        }
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, _info: Self::ArrayInfo) -> NonNull<u8> {
+        ptr.block
+    }
+
+    fn dangling() -> Self::Ptr {
+        let (block_layout, info) = Self::block_layout();
+        ChunkedPtr {
+            block: unsafe { NonNull::new_unchecked(block_layout.align() as *mut u8) },
+            lane: 0,
+            stride: block_layout.size(),
+            info: info
+        }
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        let position = ptr.lane as isize + offset;
+        let blocks = position.div_euclid(W as isize);
+        ChunkedPtr {
+            block: NonNull::new_unchecked(ptr.block.as_ptr().offset(blocks * ptr.stride as isize)),
+            lane: position.rem_euclid(W as isize) as usize,
+            stride: ptr.stride,
+            info: ptr.info
+        }
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        ptr1.block == ptr2.block && ptr1.lane == ptr2.lane
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> T {
+        Inner::read(Self::inner_ptr(ptr))
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: T) {
+        Inner::write(Self::inner_ptr(ptr), value);
+    }
+
+    unsafe fn drop_in_place(ptr: Self::Ptr) {
+        Inner::drop_in_place(Self::inner_ptr(ptr));
+    }
+
+    fn can_store(value: &T) -> bool {
+        Inner::can_store(value)
+    }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        Inner::copy_one_nonoverlapping(Self::inner_ptr(src), Self::inner_ptr(dest));
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+        Inner::swap_one_nonoverlapping(Self::inner_ptr(ptr1), Self::inner_ptr(ptr2));
+    }
+
+    // The bulk operations split the range into runs that stay within one
+    // block on both sides, and hand each run to `Inner`.
+
+    unsafe fn copy_leftwards(mut src: Self::Ptr, mut dest: Self::Ptr, mut count: usize) {
+        while count > 0 {
+            let run = cmp::min(count, W - cmp::max(src.lane, dest.lane));
+            Inner::copy_leftwards(Self::inner_ptr(src), Self::inner_ptr(dest), run);
+            src = Self::offset(src, run as isize);
+            dest = Self::offset(dest, run as isize);
+            count -= run;
+        }
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, mut count: usize) {
+        while count > 0 {
+            let src_last = Self::offset(src, count as isize - 1);
+            let dest_last = Self::offset(dest, count as isize - 1);
+            let run = cmp::min(count, cmp::min(src_last.lane, dest_last.lane) + 1);
+            count -= run;
+            Inner::copy_rightwards(Self::inner_ptr(Self::offset(src, count as isize)), Self::inner_ptr(Self::offset(dest, count as isize)), run);
+        }
+    }
+
+    unsafe fn copy_nonoverlapping(mut src: Self::Ptr, mut dest: Self::Ptr, mut count: usize) {
+        while count > 0 {
+            let run = cmp::min(count, W - cmp::max(src.lane, dest.lane));
+            Inner::copy_nonoverlapping(Self::inner_ptr(src), Self::inner_ptr(dest), run);
+            src = Self::offset(src, run as isize);
+            dest = Self::offset(dest, run as isize);
+            count -= run;
+        }
+    }
+
+    unsafe fn swap_nonoverlapping(mut ptr1: Self::Ptr, mut ptr2: Self::Ptr, mut count: usize) {
+        while count > 0 {
+            let run = cmp::min(count, W - cmp::max(ptr1.lane, ptr2.lane));
+            Inner::swap_nonoverlapping(Self::inner_ptr(ptr1), Self::inner_ptr(ptr2), run);
+            ptr1 = Self::offset(ptr1, run as isize);
+            ptr2 = Self::offset(ptr2, run as isize);
+            count -= run;
+        }
+    }
+}
+
+unsafe impl<const W: usize, T, Inner> Inspect<T> for Chunked<W, Inner> where Inner: Inspect<T>, Inner::ArrayInfo: Copy {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: Self::Ptr, func: F) -> R {
+        Inner::inspect(Self::inner_ptr(ptr), func)
+    }
+}
+
+macro_rules! chunks_exact_impl {
+    ($Iter:ident $Reference:ident $chunks_exact:ident) => {
+        impl<'a, const W: usize, T, Inner> $Reference<'a, [T], Slice<Chunked<W, Inner>>> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+            // A slice need not start at a block boundary, so the elements
+            // before the first one are kept apart as the head, just as those
+            // after the last whole block are kept as the remainder.
+            pub fn $chunks_exact(self) -> $Iter<'a, W, T, Inner> {
+                let ptr = self.as_ptr();
+                let head_len = if ptr.lane == 0 { 0 } else { cmp::min(self.len(), W - ptr.lane) };
+                let len = self.len() - head_len;
+                let start = unsafe { Chunked::<W, Inner>::offset(ptr, head_len as isize) };
+                let blocks = len / W;
+                let tail = unsafe { Chunked::<W, Inner>::offset(start, (blocks * W) as isize) };
+                $Iter {
+                    next: start,
+                    blocks: blocks,
+                    head: unsafe { $Reference::from_raw(SlicePtr::from_raw_parts(Chunked::<W, Inner>::inner_ptr(ptr), head_len)) },
+                    tail: unsafe { $Reference::from_raw(SlicePtr::from_raw_parts(Chunked::<W, Inner>::inner_ptr(tail), len % W)) },
+                    _marker: PhantomData
+                }
+            }
+        }
+
+        // Yields each block as a `W`-element slice laid out by `Inner`.
+        pub struct $Iter<'a, const W: usize, T: 'a, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+            next: ChunkedPtr<Inner::ArrayInfo>,
+            blocks: usize,
+            head: $Reference<'a, [T], Slice<Inner>>,
+            tail: $Reference<'a, [T], Slice<Inner>>,
+            _marker: PhantomData<&'a T>
+        }
+
+        impl<'a, const W: usize, T, Inner> Iterator for $Iter<'a, W, T, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+            type Item = $Reference<'a, [T], Slice<Inner>>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.blocks == 0 {
+                    return None;
+                }
+                let block = self.next;
+                self.blocks -= 1;
+                unsafe {
+                    self.next = Chunked::<W, Inner>::offset(block, W as isize);
+                    Some($Reference::from_raw(SlicePtr::from_raw_parts(Chunked::<W, Inner>::block_ptr(block), W)))
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.blocks, Some(self.blocks))
+            }
+        }
+
+        impl<'a, const W: usize, T, Inner> ExactSizeIterator for $Iter<'a, W, T, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy { }
+        impl<'a, const W: usize, T, Inner> FusedIterator for $Iter<'a, W, T, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy { }
+    }
+}
+
+chunks_exact_impl!(ChunksExact Ref chunks_exact);
+chunks_exact_impl!(ChunksExactMut RefMut chunks_exact_mut);
+
+impl<'a, const W: usize, T, Inner> ChunksExact<'a, W, T, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+    // The leading elements before the first block boundary
+    pub fn head(&self) -> Ref<'a, [T], Slice<Inner>> {
+        self.head
+    }
+
+    // The trailing elements that do not fill a whole block
+    pub fn remainder(&self) -> Ref<'a, [T], Slice<Inner>> {
+        self.tail
+    }
+}
+
+impl<'a, const W: usize, T, Inner> ChunksExactMut<'a, W, T, Inner> where Inner: ArrayLayout<T>, Inner::ArrayInfo: Copy {
+    // Takes the head out of the iterator, leaving an empty one behind, so
+    // that it can be used alongside the blocks and the remainder.
+    pub fn take_head(&mut self) -> RefMut<'a, [T], Slice<Inner>> {
+        let empty = unsafe { RefMut::from_raw(SlicePtr::from_raw_parts(self.head.as_ptr(), 0)) };
+        mem::replace(&mut self.head, empty)
+    }
+
+    pub fn into_remainder(self) -> RefMut<'a, [T], Slice<Inner>> {
+        self.tail
+    }
+}
@@ -0,0 +1,159 @@
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::slice::{Slice, SlicePtr};
+use layouts::unaligned::Unaligned;
+use layouts::{ArrayLayout, Inspect};
+
+// Numbers that can be stored in a fixed byte order. Implementors must accept
+// every bit pattern as a valid value, since `from_bytes` views arbitrary bytes
+// as `Self` without any checks.
+pub unsafe trait EndianScalar: Copy {
+    fn to_be(self) -> Self;
+    fn from_be(value: Self) -> Self;
+    fn to_le(self) -> Self;
+    fn from_le(value: Self) -> Self;
+}
+
+macro_rules! endian_int_impl {
+    ($($int:ty)*) => {
+        $(
+            unsafe impl EndianScalar for $int {
+                fn to_be(self) -> Self { <$int>::to_be(self) }
+                fn from_be(value: Self) -> Self { <$int>::from_be(value) }
+                fn to_le(self) -> Self { <$int>::to_le(self) }
+                fn from_le(value: Self) -> Self { <$int>::from_le(value) }
+            }
+        )*
+    }
+}
+
+endian_int_impl!(u16 u32 u64 u128 i16 i32 i64 i128);
+
+macro_rules! endian_float_impl {
+    ($($float:ty)*) => {
+        $(
+            unsafe impl EndianScalar for $float {
+                fn to_be(self) -> Self { <$float>::from_bits(self.to_bits().to_be()) }
+                fn from_be(value: Self) -> Self { <$float>::from_bits(EndianScalar::from_be(value.to_bits())) }
+                fn to_le(self) -> Self { <$float>::from_bits(self.to_bits().to_le()) }
+                fn from_le(value: Self) -> Self { <$float>::from_bits(EndianScalar::from_le(value.to_bits())) }
+            }
+        )*
+    }
+}
+
+endian_float_impl!(f32 f64);
+
+// Stores numbers in `Inner` in a fixed byte order, converting from and to the
+// native order on `read` and `write`. Moving elements around never needs to
+// convert anything, so the bulk operations hand whole runs to `Inner`.
+macro_rules! endian_impl {
+    ($Endian:ident $to:ident $from:ident) => {
+        pub struct $Endian<Inner> {
+            _marker: PhantomData<Inner>
+        }
+
+        unsafe impl<T: EndianScalar, Inner: ArrayLayout<T>> ArrayLayout<T> for $Endian<Inner> {
+            type Ptr = Inner::Ptr;
+            type ArrayInfo = Inner::ArrayInfo;
+
+            fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+                Inner::layout_array(count)
+            }
+
+            unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+                Inner::from_flat_ptr(ptr, info)
+            }
+
+            unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+                Inner::initialize(ptr, count);
+            }
+
+            unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+                Inner::base_ptr(ptr, info)
+            }
+
+            fn dangling() -> Self::Ptr {
+                Inner::dangling()
+            }
+
+            unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+                Inner::offset(ptr, offset)
+            }
+
+            unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+                Inner::same_ptr(ptr1, ptr2)
+            }
+
+            unsafe fn read(ptr: Self::Ptr) -> T {
+                T::$from(Inner::read(ptr))
+            }
+
+            unsafe fn write(ptr: Self::Ptr, value: T) {
+                Inner::write(ptr, value.$to());
+            }
+
+            unsafe fn drop_in_place(_ptr: Self::Ptr) { }
+
+            fn can_store(value: &T) -> bool {
+                Inner::can_store(&value.$to())
+            }
+
+            unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+                Inner::copy_one_nonoverlapping(src, dest);
+            }
+
+            unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+                Inner::swap_one_nonoverlapping(ptr1, ptr2);
+            }
+
+            unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                Inner::copy_leftwards(src, dest, count);
+            }
+
+            unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                Inner::copy_rightwards(src, dest, count);
+            }
+
+            unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                Inner::copy_nonoverlapping(src, dest, count);
+            }
+
+            unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+                Inner::swap_nonoverlapping(ptr1, ptr2, count);
+            }
+        }
+
+        unsafe impl<T: EndianScalar, Inner: ArrayLayout<T>> Inspect<T> for $Endian<Inner> {
+            unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: Self::Ptr, func: F) -> R {
+                func(&Self::read(ptr))
+            }
+        }
+
+        // Unlike for arbitrary records, any bytes hold valid numbers, so
+        // viewing a buffer is safe. Trailing bytes are ignored.
+        impl<'a, T: EndianScalar> Ref<'a, [T], Slice<$Endian<Unaligned>>> {
+            pub fn from_bytes(bytes: &'a [u8]) -> Self {
+                unsafe {
+                    Ref::from_raw(SlicePtr::from_raw_parts(NonNull::from(bytes).cast(), bytes.len() / size_of::<T>()))
+                }
+            }
+        }
+
+        impl<'a, T: EndianScalar> RefMut<'a, [T], Slice<$Endian<Unaligned>>> {
+            pub fn from_bytes(bytes: &'a mut [u8]) -> Self {
+                let len = bytes.len() / size_of::<T>();
+                unsafe {
+                    RefMut::from_raw(SlicePtr::from_raw_parts(NonNull::from(bytes).cast(), len))
+                }
+            }
+        }
+    }
+}
+
+endian_impl!(BigEndian to_be from_be);
+endian_impl!(LittleEndian to_le from_le);
@@ -0,0 +1,34 @@
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::flat::Flat;
+use layouts::slice::{Slice, SlicePtr};
+use layouts::ArrayLayout;
+
+// A layout for enums that keeps the discriminants in their own `u8` column,
+// next to the columns holding the fields of each variant. The field slots
+// of an element are only initialized for the variant named by its tag.
+// Implemented by `#[derive(ArrayLayout)]` on enums.
+pub unsafe trait EnumLayout<T>: ArrayLayout<T> {
+    fn tag_ptr(ptr: Self::Ptr) -> NonNull<u8>;
+}
+
+impl<'a, T, TLayout: EnumLayout<T>> Ref<'a, T, TLayout> {
+    pub fn tag(&self) -> u8 {
+        unsafe { *TLayout::tag_ptr(self.as_raw()).as_ptr() }
+    }
+}
+
+impl<'a, T, TLayout: EnumLayout<T>> RefMut<'a, T, TLayout> {
+    pub fn tag(&self) -> u8 {
+        unsafe { *TLayout::tag_ptr(self.as_raw()).as_ptr() }
+    }
+}
+
+impl<'a, T, TLayout: EnumLayout<T>> Ref<'a, [T], Slice<TLayout>> {
+    // The discriminant column alone, for scanning by variant without
+    // touching any fields.
+    pub fn tags(self) -> Ref<'a, [u8], Slice<Flat>> {
+        unsafe { Ref::from_raw(SlicePtr::from_raw_parts(TLayout::tag_ptr(self.as_ptr()), self.len())) }
+    }
+}
@@ -2,7 +2,7 @@ use core::alloc::Layout;
 use core::ptr::NonNull;
 use core::marker::PhantomData;
 
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
 
 pub struct Extra<T> {
     _marker: PhantomData<T>
@@ -40,7 +40,7 @@ unsafe impl<T> ArrayLayout<()> for Extra<T> {
         true
     }
 
-    unsafe fn read(_ptr: Self::Ptr) -> () { }
+    unsafe fn read(_ptr: Self::Ptr) { }
     unsafe fn write(_ptr: Self::Ptr, _value: ()) { }
     unsafe fn drop_in_place(_ptr: Self::Ptr) { }
     unsafe fn copy_one_nonoverlapping(_src: Self::Ptr, _dest: Self::Ptr) { }
@@ -50,3 +50,9 @@ unsafe impl<T> ArrayLayout<()> for Extra<T> {
     unsafe fn copy_nonoverlapping(_src: Self::Ptr, _dest: Self::Ptr, _count: usize) { }
     unsafe fn swap_nonoverlapping(_ptr1: Self::Ptr, _ptr2: Self::Ptr, _count: usize) { }
 }
+
+unsafe impl<T> Inspect<()> for Extra<T> {
+    unsafe fn inspect<R, F: FnOnce(&()) -> R>(_ptr: Self::Ptr, func: F) -> R {
+        func(&())
+    }
+}
@@ -0,0 +1,259 @@
+use core::alloc::Layout;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::bitvec::{Ones, PackedBits};
+use layouts::flat::Flat;
+use layouts::slice::{Slice, SlicePtr};
+use layouts::{ArrayLayout, Inspect};
+
+// Stores `Result<T, E>` as an `is_ok` mask next to a column of successes and
+// a column of errors. Each element only initializes the slot its mask bit
+// selects; the other is left uninitialized and never read or dropped.
+pub struct Fallible<OkLayout, ErrLayout, MaskLayout = PackedBits<Flat>> {
+    _marker: PhantomData<(OkLayout, ErrLayout, MaskLayout)>
+}
+
+// Moves the initialized slot of one element to another.
+unsafe fn move_one<T, E, OkLayout, ErrLayout, MaskLayout>(src: (OkLayout::Ptr, ErrLayout::Ptr, MaskLayout::Ptr), dest: (OkLayout::Ptr, ErrLayout::Ptr, MaskLayout::Ptr))
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    if MaskLayout::read(src.2) {
+        OkLayout::copy_one_nonoverlapping(src.0, dest.0);
+    } else {
+        ErrLayout::copy_one_nonoverlapping(src.1, dest.1);
+    }
+}
+
+// Whether both value columns of `src` and `dest` are the same slots, so that
+// nothing needs to move. Zero-sized columns may spuriously compare equal, but
+// moving their elements does nothing anyway.
+unsafe fn same_values<T, E, OkLayout, ErrLayout, MaskLayout>(src: (OkLayout::Ptr, ErrLayout::Ptr, MaskLayout::Ptr), dest: (OkLayout::Ptr, ErrLayout::Ptr, MaskLayout::Ptr)) -> bool
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    OkLayout::same_ptr(src.0, dest.0) && ErrLayout::same_ptr(src.1, dest.1)
+}
+
+unsafe impl<T, E, OkLayout, ErrLayout, MaskLayout> ArrayLayout<Result<T, E>> for Fallible<OkLayout, ErrLayout, MaskLayout>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    type Ptr = (OkLayout::Ptr, ErrLayout::Ptr, MaskLayout::Ptr);
+    type ArrayInfo = ((OkLayout::ArrayInfo, usize), (ErrLayout::ArrayInfo, usize), (MaskLayout::ArrayInfo, usize));
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let (ok_layout, ok_info) = OkLayout::layout_array(count);
+        let (err_layout, err_info) = ErrLayout::layout_array(count);
+        let (mask_layout, mask_info) = MaskLayout::layout_array(count);
+        let (combined_layout, ok_offset) = Layout::new::<()>().extend(ok_layout).expect("Overflow in combining array layouts");
+        let (combined_layout, err_offset) = combined_layout.extend(err_layout).expect("Overflow in combining array layouts");
+        let (combined_layout, mask_offset) = combined_layout.extend(mask_layout).expect("Overflow in combining array layouts");
+        (combined_layout, ((ok_info, ok_offset), (err_info, err_offset), (mask_info, mask_offset)))
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        let (ok_info, err_info, mask_info) = info;
+        (
+            OkLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(ok_info.1)), ok_info.0),
+            ErrLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(err_info.1)), err_info.0),
+            MaskLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(mask_info.1)), mask_info.0)
+        )
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        OkLayout::initialize(ptr.0, count);
+        ErrLayout::initialize(ptr.1, count);
+        MaskLayout::initialize(ptr.2, count);
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+        OkLayout::base_ptr(ptr.0, (info.0).0)
+    }
+
+    fn dangling() -> Self::Ptr {
+        (OkLayout::dangling(), ErrLayout::dangling(), MaskLayout::dangling())
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        (OkLayout::offset(ptr.0, offset), ErrLayout::offset(ptr.1, offset), MaskLayout::offset(ptr.2, offset))
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        if mem::size_of::<T>() > 0 {
+            OkLayout::same_ptr(ptr1.0, ptr2.0)
+        } else if mem::size_of::<E>() > 0 {
+            ErrLayout::same_ptr(ptr1.1, ptr2.1)
+        } else {
+            MaskLayout::same_ptr(ptr1.2, ptr2.2)
+        }
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> Result<T, E> {
+        if MaskLayout::read(ptr.2) {
+            Ok(OkLayout::read(ptr.0))
+        } else {
+            Err(ErrLayout::read(ptr.1))
+        }
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: Result<T, E>) {
+        MaskLayout::write(ptr.2, value.is_ok());
+        match value {
+            Ok(value) => OkLayout::write(ptr.0, value),
+            Err(error) => ErrLayout::write(ptr.1, error)
+        }
+    }
+
+    unsafe fn drop_in_place(ptr: Self::Ptr) {
+        if MaskLayout::read(ptr.2) {
+            OkLayout::drop_in_place(ptr.0);
+        } else {
+            ErrLayout::drop_in_place(ptr.1);
+        }
+    }
+
+    fn can_store(value: &Result<T, E>) -> bool {
+        match *value {
+            Ok(ref value) => OkLayout::can_store(value),
+            Err(ref error) => ErrLayout::can_store(error)
+        }
+    }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        move_one::<T, E, OkLayout, ErrLayout, MaskLayout>(src, dest);
+        MaskLayout::copy_one_nonoverlapping(src.2, dest.2);
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+        let value1 = Self::read(ptr1);
+        let value2 = Self::read(ptr2);
+        Self::write(ptr1, value2);
+        Self::write(ptr2, value1);
+    }
+
+    // As with `Nullable`, only the mask moves in bulk, after the initialized
+    // slots have been moved one by one, and moving a run onto itself leaves
+    // the values alone.
+
+    unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !same_values::<T, E, OkLayout, ErrLayout, MaskLayout>(src, dest) {
+            for i in 0..count as isize {
+                move_one::<T, E, OkLayout, ErrLayout, MaskLayout>(Self::offset(src, i), Self::offset(dest, i));
+            }
+        }
+        MaskLayout::copy_leftwards(src.2, dest.2, count);
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !same_values::<T, E, OkLayout, ErrLayout, MaskLayout>(src, dest) {
+            for i in (0..count as isize).rev() {
+                move_one::<T, E, OkLayout, ErrLayout, MaskLayout>(Self::offset(src, i), Self::offset(dest, i));
+            }
+        }
+        MaskLayout::copy_rightwards(src.2, dest.2, count);
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !same_values::<T, E, OkLayout, ErrLayout, MaskLayout>(src, dest) {
+            for i in 0..count as isize {
+                move_one::<T, E, OkLayout, ErrLayout, MaskLayout>(Self::offset(src, i), Self::offset(dest, i));
+            }
+        }
+        MaskLayout::copy_nonoverlapping(src.2, dest.2, count);
+    }
+}
+
+unsafe impl<T: Copy, E: Copy, OkLayout, ErrLayout, MaskLayout> Inspect<Result<T, E>> for Fallible<OkLayout, ErrLayout, MaskLayout>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    unsafe fn inspect<R, F: FnOnce(&Result<T, E>) -> R>(ptr: Self::Ptr, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
+
+impl<'a, T, E, OkLayout, ErrLayout, MaskLayout> Ref<'a, Result<T, E>, Fallible<OkLayout, ErrLayout, MaskLayout>>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    pub fn is_ok(&self) -> bool {
+        unsafe { MaskLayout::read(self.as_raw().2) }
+    }
+
+    pub fn is_err(&self) -> bool {
+        !self.is_ok()
+    }
+
+    pub fn as_result(self) -> Result<Ref<'a, T, OkLayout>, Ref<'a, E, ErrLayout>> {
+        unsafe {
+            if self.is_ok() {
+                Ok(Ref::from_raw(self.as_raw().0))
+            } else {
+                Err(Ref::from_raw(self.as_raw().1))
+            }
+        }
+    }
+}
+
+impl<'a, T, E, OkLayout, ErrLayout, MaskLayout> RefMut<'a, Result<T, E>, Fallible<OkLayout, ErrLayout, MaskLayout>>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    pub fn is_ok(&self) -> bool {
+        unsafe { MaskLayout::read(self.as_raw().2) }
+    }
+
+    pub fn is_err(&self) -> bool {
+        !self.is_ok()
+    }
+
+    pub fn as_result_mut(self) -> Result<RefMut<'a, T, OkLayout>, RefMut<'a, E, ErrLayout>> {
+        unsafe {
+            if self.is_ok() {
+                Ok(RefMut::from_raw(self.as_raw().0))
+            } else {
+                Err(RefMut::from_raw(self.as_raw().1))
+            }
+        }
+    }
+}
+
+impl<'a, T, E, OkLayout, ErrLayout, MaskLayout> Ref<'a, [Result<T, E>], Slice<Fallible<OkLayout, ErrLayout, MaskLayout>>>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, MaskLayout: ArrayLayout<bool> {
+    pub fn ok_mask(self) -> Ref<'a, [bool], Slice<MaskLayout>> {
+        unsafe { Ref::from_raw(SlicePtr::from_raw_parts(self.as_ptr().2, self.len())) }
+    }
+}
+
+impl<'a, T, E, OkLayout, ErrLayout, WordLayout> Ref<'a, [Result<T, E>], Slice<Fallible<OkLayout, ErrLayout, PackedBits<WordLayout>>>>
+        where OkLayout: ArrayLayout<T>, ErrLayout: ArrayLayout<E>, WordLayout: ArrayLayout<usize> {
+    pub fn ok_count(self) -> usize {
+        self.ok_mask().count_ones()
+    }
+
+    pub fn err_count(self) -> usize {
+        self.ok_mask().count_zeros()
+    }
+
+    // Visits only the successes, skipping whole words of errors at a time.
+    pub fn oks(self) -> Oks<'a, T, OkLayout, WordLayout> {
+        Oks {
+            positions: self.ok_mask().ones(),
+            base: self.as_ptr().0,
+            _marker: PhantomData
+        }
+    }
+}
+
+pub struct Oks<'a, T: 'a, OkLayout: ArrayLayout<T>, WordLayout: ArrayLayout<usize>> {
+    positions: Ones<'a, WordLayout>,
+    base: OkLayout::Ptr,
+    _marker: PhantomData<&'a T>
+}
+
+impl<'a, T, OkLayout: ArrayLayout<T>, WordLayout: ArrayLayout<usize>> Iterator for Oks<'a, T, OkLayout, WordLayout> {
+    type Item = Ref<'a, T, OkLayout>;
+
+    fn next(&mut self) -> Option<Ref<'a, T, OkLayout>> {
+        self.positions.next().map(|index| unsafe { Ref::from_raw(OkLayout::offset(self.base, index as isize)) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.positions.size_hint()
+    }
+}
+
+impl<'a, T, OkLayout: ArrayLayout<T>, WordLayout: ArrayLayout<usize>> FusedIterator for Oks<'a, T, OkLayout, WordLayout> { }
@@ -1,7 +1,7 @@
 use core::alloc::Layout;
 use core::ptr::{self, NonNull};
 
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
 
 pub struct Flat {
     _priv: ()
@@ -73,3 +73,9 @@ unsafe impl<T> ArrayLayout<T> for Flat {
         ptr::swap_nonoverlapping(ptr1.as_ptr(), ptr2.as_ptr(), count);
     }
 }
+
+unsafe impl<T> Inspect<T> for Flat {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: NonNull<T>, func: F) -> R {
+        func(&*ptr.as_ptr())
+    }
+}
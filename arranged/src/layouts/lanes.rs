@@ -0,0 +1,164 @@
+use core::alloc::Layout;
+use core::array;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::slice::{Slice, SlicePtr};
+use layouts::{ArrayLayout, Inspect};
+
+// Stores `[T; N]` as `N` separate arrays, one per lane, each laid out by
+// `Inner`. This is `Parallel` for homogeneous tuples of any length.
+pub struct ArrayOfLanes<const N: usize, Inner> {
+    _marker: PhantomData<Inner>
+}
+
+impl<const N: usize, Inner> ArrayOfLanes<N, Inner> {
+    // Without lanes there would be nothing to take a base pointer from.
+    const NONEMPTY: () = assert!(N > 0, "ArrayOfLanes needs at least one lane");
+}
+
+unsafe impl<const N: usize, T, Inner: ArrayLayout<T>> ArrayLayout<[T; N]> for ArrayOfLanes<N, Inner> {
+    type Ptr = [Inner::Ptr; N];
+    type ArrayInfo = [(Inner::ArrayInfo, usize); N];
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let () = Self::NONEMPTY;
+        let mut combined_layout = Layout::new::<()>();
+        let info = array::from_fn(|_| {
+            let (layout, info) = Inner::layout_array(count);
+            let (new_layout, offset) = combined_layout.extend(layout).expect("Overflow in combining array layouts");
+            combined_layout = new_layout;
+            (info, offset)
+        });
+        (combined_layout, info)
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        let mut info = IntoIterator::into_iter(info);
+        array::from_fn(|_| {
+            let (info, offset) = info.next().unwrap();
+            Inner::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(offset)), info)
+        })
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        for lane in ptr {
+            Inner::initialize(lane, count);
+        }
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+        let (info, _) = IntoIterator::into_iter(info).next().unwrap();
+        Inner::base_ptr(ptr[0], info)
+    }
+
+    fn dangling() -> Self::Ptr {
+        let () = Self::NONEMPTY;
+        array::from_fn(|_| Inner::dangling())
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        ptr.map(|lane| Inner::offset(lane, offset))
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        Inner::same_ptr(ptr1[0], ptr2[0])
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> [T; N] {
+        ptr.map(|lane| Inner::read(lane))
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: [T; N]) {
+        for (lane, value) in ptr.iter().zip(IntoIterator::into_iter(value)) {
+            Inner::write(*lane, value);
+        }
+    }
+
+    unsafe fn drop_in_place(ptr: Self::Ptr) {
+        for lane in ptr {
+            Inner::drop_in_place(lane);
+        }
+    }
+
+    fn can_store(value: &[T; N]) -> bool {
+        value.iter().all(Inner::can_store)
+    }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        for k in 0..N {
+            Inner::copy_one_nonoverlapping(src[k], dest[k]);
+        }
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+        for k in 0..N {
+            Inner::swap_one_nonoverlapping(ptr1[k], ptr2[k]);
+        }
+    }
+
+    unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        for k in 0..N {
+            Inner::copy_leftwards(src[k], dest[k], count);
+        }
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        for k in 0..N {
+            Inner::copy_rightwards(src[k], dest[k], count);
+        }
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        for k in 0..N {
+            Inner::copy_nonoverlapping(src[k], dest[k], count);
+        }
+    }
+
+    unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+        for k in 0..N {
+            Inner::swap_nonoverlapping(ptr1[k], ptr2[k], count);
+        }
+    }
+}
+
+unsafe impl<const N: usize, T: Copy, Inner: ArrayLayout<T>> Inspect<[T; N]> for ArrayOfLanes<N, Inner> {
+    unsafe fn inspect<R, F: FnOnce(&[T; N]) -> R>(ptr: Self::Ptr, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
+
+impl<'a, const N: usize, T, Inner: ArrayLayout<T>> Ref<'a, [T; N], ArrayOfLanes<N, Inner>> {
+    pub fn split(self) -> [Ref<'a, T, Inner>; N] {
+        self.as_raw().map(|lane| unsafe { Ref::from_raw(lane) })
+    }
+}
+
+impl<'a, const N: usize, T, Inner: ArrayLayout<T>> RefMut<'a, [T; N], ArrayOfLanes<N, Inner>> {
+    pub fn split(self) -> [RefMut<'a, T, Inner>; N] {
+        self.as_raw().map(|lane| unsafe { RefMut::from_raw(lane) })
+    }
+}
+
+impl<'a, const N: usize, T, Inner: ArrayLayout<T>> Ref<'a, [[T; N]], Slice<ArrayOfLanes<N, Inner>>> {
+    pub fn lane(self, k: usize) -> Ref<'a, [T], Slice<Inner>> {
+        unsafe { Ref::from_raw(SlicePtr::from_raw_parts(self.as_ptr()[k], self.len())) }
+    }
+
+    pub fn lanes(self) -> [Ref<'a, [T], Slice<Inner>>; N] {
+        let len = self.len();
+        self.as_ptr().map(|lane| unsafe { Ref::from_raw(SlicePtr::from_raw_parts(lane, len)) })
+    }
+}
+
+impl<'a, const N: usize, T, Inner: ArrayLayout<T>> RefMut<'a, [[T; N]], Slice<ArrayOfLanes<N, Inner>>> {
+    pub fn lane(self, k: usize) -> RefMut<'a, [T], Slice<Inner>> {
+        unsafe { RefMut::from_raw(SlicePtr::from_raw_parts(self.as_ptr()[k], self.len())) }
+    }
+
+    pub fn lanes(self) -> [RefMut<'a, [T], Slice<Inner>>; N] {
+        let len = self.len();
+        self.as_ptr().map(|lane| unsafe { RefMut::from_raw(SlicePtr::from_raw_parts(lane, len)) })
+    }
+}
@@ -1,16 +1,34 @@
+pub mod aligned;
 pub mod bitvec;
+pub mod chunked;
+pub mod endian;
+pub mod enums;
 pub mod extra;
+pub mod fallible;
 pub mod flat;
+pub mod lanes;
+pub mod nullable;
+pub mod packedints;
 pub mod parallel;
 pub mod slice;
 pub mod strided;
+pub mod unaligned;
 
+pub use self::aligned::Aligned;
+pub use self::chunked::Chunked;
+pub use self::endian::{BigEndian, LittleEndian};
+pub use self::enums::EnumLayout;
 pub use self::extra::Extra;
+pub use self::fallible::Fallible;
 pub use self::flat::Flat;
+pub use self::lanes::ArrayOfLanes;
+pub use self::nullable::Nullable;
 pub use self::parallel::Parallel;
 pub use self::slice::Slice;
 pub use self::strided::Strided;
+pub use self::unaligned::Unaligned;
 pub use self::bitvec::PackedBits;
+pub use self::packedints::PackedInts;
 
 use core::alloc::Layout;
 use core::ptr::NonNull;
@@ -32,6 +50,13 @@ pub unsafe trait ArrayLayout<T: ?Sized> {
     unsafe fn write(ptr: Self::Ptr, value: T) where T: Sized;
     unsafe fn drop_in_place(ptr: Self::Ptr);
 
+    // Whether `write` stores `value` exactly. Safe code asserts this before
+    // moving anything, so that a value the layout cannot represent panics
+    // without leaving the array half shifted.
+    fn can_store(_value: &T) -> bool where T: Sized {
+        true
+    }
+
     unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) where T: Sized {
         Self::write(dest, Self::read(src));
     }
@@ -71,3 +96,9 @@ pub unsafe trait ArrayLayout<T: ?Sized> {
         }
     }
 }
+
+// Layouts that can lend out a `&T` for an element: either a `T` really lives
+// in memory, or `T: Copy` so a temporary copy cannot be told apart from it.
+pub unsafe trait Inspect<T>: ArrayLayout<T> {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: Self::Ptr, func: F) -> R;
+}
@@ -0,0 +1,228 @@
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use reference::{Ref, RefMut};
+use layouts::bitvec::PackedBits;
+use layouts::flat::Flat;
+use layouts::slice::{Slice, SlicePtr};
+use layouts::{ArrayLayout, Inspect};
+
+// Stores `Option<T>` as a column of values next to a validity mask. The value
+// slot of a `None` is left uninitialized, so values are only ever read, moved
+// or dropped where the mask is set.
+pub struct Nullable<ValueLayout, MaskLayout = PackedBits<Flat>> {
+    _marker: PhantomData<(ValueLayout, MaskLayout)>
+}
+
+unsafe impl<T, ValueLayout, MaskLayout> ArrayLayout<Option<T>> for Nullable<ValueLayout, MaskLayout>
+        where ValueLayout: ArrayLayout<T>, MaskLayout: ArrayLayout<bool> {
+    type Ptr = (ValueLayout::Ptr, MaskLayout::Ptr);
+    type ArrayInfo = ((ValueLayout::ArrayInfo, usize), (MaskLayout::ArrayInfo, usize));
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let (value_layout, value_info) = ValueLayout::layout_array(count);
+        let (mask_layout, mask_info) = MaskLayout::layout_array(count);
+        let (combined_layout, value_offset) = Layout::new::<()>().extend(value_layout).expect("Overflow in combining array layouts");
+        let (combined_layout, mask_offset) = combined_layout.extend(mask_layout).expect("Overflow in combining array layouts");
+        (combined_layout, ((value_info, value_offset), (mask_info, mask_offset)))
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        let (value_info, mask_info) = info;
+        (
+            ValueLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(value_info.1)), value_info.0),
+            MaskLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(mask_info.1)), mask_info.0)
+        )
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        ValueLayout::initialize(ptr.0, count);
+        MaskLayout::initialize(ptr.1, count);
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+        ValueLayout::base_ptr(ptr.0, (info.0).0)
+    }
+
+    fn dangling() -> Self::Ptr {
+        (ValueLayout::dangling(), MaskLayout::dangling())
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        (ValueLayout::offset(ptr.0, offset), MaskLayout::offset(ptr.1, offset))
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        if mem::size_of::<T>() > 0 {
+            ValueLayout::same_ptr(ptr1.0, ptr2.0)
+        } else {
+            MaskLayout::same_ptr(ptr1.1, ptr2.1)
+        }
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> Option<T> {
+        if MaskLayout::read(ptr.1) {
+            Some(ValueLayout::read(ptr.0))
+        } else {
+            None
+        }
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: Option<T>) {
+        MaskLayout::write(ptr.1, value.is_some());
+        if let Some(value) = value {
+            ValueLayout::write(ptr.0, value);
+        }
+    }
+
+    unsafe fn drop_in_place(ptr: Self::Ptr) {
+        if MaskLayout::read(ptr.1) {
+            ValueLayout::drop_in_place(ptr.0);
+        }
+    }
+
+    fn can_store(value: &Option<T>) -> bool {
+        match *value {
+            Some(ref value) => ValueLayout::can_store(value),
+            None => true
+        }
+    }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        let is_some = MaskLayout::read(src.1);
+        if is_some {
+            ValueLayout::copy_one_nonoverlapping(src.0, dest.0);
+        }
+        MaskLayout::write(dest.1, is_some);
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+        let is_some1 = MaskLayout::read(ptr1.1);
+        let is_some2 = MaskLayout::read(ptr2.1);
+        match (is_some1, is_some2) {
+            (true, true) => ValueLayout::swap_one_nonoverlapping(ptr1.0, ptr2.0),
+            (true, false) => ValueLayout::copy_one_nonoverlapping(ptr1.0, ptr2.0),
+            (false, true) => ValueLayout::copy_one_nonoverlapping(ptr2.0, ptr1.0),
+            (false, false) => { }
+        }
+        MaskLayout::write(ptr1.1, is_some2);
+        MaskLayout::write(ptr2.1, is_some1);
+    }
+
+    // Only the mask moves in bulk: the value layout may not be able to move
+    // uninitialized slots, so values are moved one by one where the source
+    // mask is set, before the mask itself is overwritten. Moving a run onto
+    // itself (e.g. an empty `drain`) must not touch the values, since
+    // `copy_one_nonoverlapping` may not be called with equal pointers.
+
+    unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !ValueLayout::same_ptr(src.0, dest.0) {
+            for i in 0..count as isize {
+                if MaskLayout::read(MaskLayout::offset(src.1, i)) {
+                    ValueLayout::copy_one_nonoverlapping(ValueLayout::offset(src.0, i), ValueLayout::offset(dest.0, i));
+                }
+            }
+        }
+        MaskLayout::copy_leftwards(src.1, dest.1, count);
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !ValueLayout::same_ptr(src.0, dest.0) {
+            for i in (0..count as isize).rev() {
+                if MaskLayout::read(MaskLayout::offset(src.1, i)) {
+                    ValueLayout::copy_one_nonoverlapping(ValueLayout::offset(src.0, i), ValueLayout::offset(dest.0, i));
+                }
+            }
+        }
+        MaskLayout::copy_rightwards(src.1, dest.1, count);
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        if !ValueLayout::same_ptr(src.0, dest.0) {
+            for i in 0..count as isize {
+                if MaskLayout::read(MaskLayout::offset(src.1, i)) {
+                    ValueLayout::copy_one_nonoverlapping(ValueLayout::offset(src.0, i), ValueLayout::offset(dest.0, i));
+                }
+            }
+        }
+        MaskLayout::copy_nonoverlapping(src.1, dest.1, count);
+    }
+
+    unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+        for i in 0..count as isize {
+            let value1 = ValueLayout::offset(ptr1.0, i);
+            let value2 = ValueLayout::offset(ptr2.0, i);
+            match (MaskLayout::read(MaskLayout::offset(ptr1.1, i)), MaskLayout::read(MaskLayout::offset(ptr2.1, i))) {
+                (true, true) => ValueLayout::swap_one_nonoverlapping(value1, value2),
+                (true, false) => ValueLayout::copy_one_nonoverlapping(value1, value2),
+                (false, true) => ValueLayout::copy_one_nonoverlapping(value2, value1),
+                (false, false) => { }
+            }
+        }
+        MaskLayout::swap_nonoverlapping(ptr1.1, ptr2.1, count);
+    }
+}
+
+unsafe impl<T: Copy, ValueLayout, MaskLayout> Inspect<Option<T>> for Nullable<ValueLayout, MaskLayout>
+        where ValueLayout: ArrayLayout<T>, MaskLayout: ArrayLayout<bool> {
+    unsafe fn inspect<R, F: FnOnce(&Option<T>) -> R>(ptr: Self::Ptr, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
+
+impl<'a, T, ValueLayout, MaskLayout> Ref<'a, Option<T>, Nullable<ValueLayout, MaskLayout>>
+        where ValueLayout: ArrayLayout<T>, MaskLayout: ArrayLayout<bool> {
+    pub fn is_some(&self) -> bool {
+        unsafe { MaskLayout::read(self.as_raw().1) }
+    }
+
+    pub fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    pub fn as_option(self) -> Option<Ref<'a, T, ValueLayout>> {
+        if self.is_some() {
+            Some(unsafe { Ref::from_raw(self.as_raw().0) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T, ValueLayout, MaskLayout> RefMut<'a, Option<T>, Nullable<ValueLayout, MaskLayout>>
+        where ValueLayout: ArrayLayout<T>, MaskLayout: ArrayLayout<bool> {
+    pub fn is_some(&self) -> bool {
+        unsafe { MaskLayout::read(self.as_raw().1) }
+    }
+
+    pub fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    pub fn as_option_mut(self) -> Option<RefMut<'a, T, ValueLayout>> {
+        if self.is_some() {
+            Some(unsafe { RefMut::from_raw(self.as_raw().0) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T, ValueLayout, MaskLayout> Ref<'a, [Option<T>], Slice<Nullable<ValueLayout, MaskLayout>>>
+        where ValueLayout: ArrayLayout<T>, MaskLayout: ArrayLayout<bool> {
+    // The validity mask, with a set bit for every `Some`. There is no
+    // corresponding view of the values, since the slots of `None`s are
+    // uninitialized.
+    pub fn validity(self) -> Ref<'a, [bool], Slice<MaskLayout>> {
+        unsafe { Ref::from_raw(SlicePtr::from_raw_parts(self.as_ptr().1, self.len())) }
+    }
+}
+
+impl<'a, T, ValueLayout, WordLayout> Ref<'a, [Option<T>], Slice<Nullable<ValueLayout, PackedBits<WordLayout>>>>
+        where ValueLayout: ArrayLayout<T>, WordLayout: ArrayLayout<usize> {
+    pub fn null_count(self) -> usize {
+        self.validity().count_zeros()
+    }
+}
@@ -0,0 +1,159 @@
+use core::alloc::Layout;
+use core::cmp;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use layouts::{ArrayLayout, Inspect};
+use layouts::bitvec::{BitPtr, PackedBits, USIZE_BITS, read_bits, write_bits};
+
+// Stores every value in exactly `BITS` bits, back to back, so values may
+// straddle word boundaries. Addressing and bulk moves are those of the
+// underlying `PackedBits`, scaled by `BITS`.
+pub struct PackedInts<const BITS: u32, WordLayout> {
+    _marker: PhantomData<WordLayout>
+}
+
+pub trait PackedInt: Copy {
+    const BITS: u32;
+
+    fn to_bits(self) -> u64;
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! packed_int_impl {
+    ($($int:ty)*) => {
+        $(
+            impl PackedInt for $int {
+                const BITS: u32 = <$int>::BITS;
+
+                fn to_bits(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_bits(bits: u64) -> Self {
+                    bits as $int
+                }
+            }
+        )*
+    }
+}
+
+packed_int_impl!(u8 u16 u32 u64);
+
+// Rejects widths that are zero or too large for `T` when the layout is used.
+struct CheckWidth<T, const BITS: u32> {
+    _marker: PhantomData<T>
+}
+
+impl<T: PackedInt, const BITS: u32> CheckWidth<T, BITS> {
+    const VALID: () = assert!(BITS > 0 && BITS <= T::BITS, "PackedInts width must be between 1 and the width of the integer type");
+}
+
+impl<const BITS: u32, WordLayout> PackedInts<BITS, WordLayout> {
+    pub fn fits<T: PackedInt>(value: T) -> bool {
+        BITS >= 64 || value.to_bits() >> BITS == 0
+    }
+}
+
+unsafe fn bit_offset<WordLayout: ArrayLayout<usize>>(ptr: BitPtr<WordLayout>, bits: usize) -> BitPtr<WordLayout> {
+    <PackedBits<WordLayout> as ArrayLayout<bool>>::offset(ptr, bits as isize)
+}
+
+// Reads `bits` bits a word-sized chunk at a time, since `u64` values may be
+// wider than a word.
+unsafe fn read_value<WordLayout: ArrayLayout<usize>>(ptr: BitPtr<WordLayout>, bits: usize) -> u64 {
+    let mut value = 0;
+    let mut done = 0;
+    while done < bits {
+        let len = cmp::min(USIZE_BITS, bits - done);
+        value |= (read_bits(bit_offset(ptr, done), len) as u64) << done;
+        done += len;
+    }
+    value
+}
+
+unsafe fn write_value<WordLayout: ArrayLayout<usize>>(ptr: BitPtr<WordLayout>, bits: usize, value: u64) {
+    let mut done = 0;
+    while done < bits {
+        let len = cmp::min(USIZE_BITS, bits - done);
+        write_bits(bit_offset(ptr, done), len, (value >> done) as usize);
+        done += len;
+    }
+}
+
+unsafe impl<const BITS: u32, WordLayout: ArrayLayout<usize>, T: PackedInt> ArrayLayout<T> for PackedInts<BITS, WordLayout> {
+    type Ptr = BitPtr<WordLayout>;
+    type ArrayInfo = WordLayout::ArrayInfo;
+
+    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+        let () = CheckWidth::<T, BITS>::VALID;
+        let bit_count = count.checked_mul(BITS as usize).expect("Overflow in calculating array layout");
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::layout_array(bit_count)
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::from_flat_ptr(ptr, info)
+    }
+
+    unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::initialize(ptr, count * BITS as usize);
+    }
+
+    unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::base_ptr(ptr, info)
+    }
+
+    fn dangling() -> Self::Ptr {
+        let () = CheckWidth::<T, BITS>::VALID;
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::dangling()
+    }
+
+    unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::offset(ptr, offset * BITS as isize)
+    }
+
+    unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::same_ptr(ptr1, ptr2)
+    }
+
+    unsafe fn read(ptr: Self::Ptr) -> T {
+        T::from_bits(read_value(ptr, BITS as usize))
+    }
+
+    unsafe fn write(ptr: Self::Ptr, value: T) {
+        debug_assert!(Self::can_store(&value), "value does not fit in {} bits", BITS);
+        write_value(ptr, BITS as usize, value.to_bits());
+    }
+
+    fn can_store(value: &T) -> bool {
+        Self::fits(*value)
+    }
+
+    unsafe fn drop_in_place(_ptr: Self::Ptr) { }
+
+    unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+        write_value(dest, BITS as usize, read_value(src, BITS as usize));
+    }
+
+    unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::copy_leftwards(src, dest, count * BITS as usize);
+    }
+
+    unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::copy_rightwards(src, dest, count * BITS as usize);
+    }
+
+    unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::copy_nonoverlapping(src, dest, count * BITS as usize);
+    }
+
+    unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+        <PackedBits<WordLayout> as ArrayLayout<bool>>::swap_nonoverlapping(ptr1, ptr2, count * BITS as usize);
+    }
+}
+
+unsafe impl<const BITS: u32, WordLayout: ArrayLayout<usize>, T: PackedInt> Inspect<T> for PackedInts<BITS, WordLayout> {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: Self::Ptr, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
@@ -1,127 +1,196 @@
 use core::alloc::Layout;
+#[cfg(feature = "alloc")]
+use core::cmp::Ordering;
 use core::ptr::NonNull;
 
 use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 use reference::{Ref, RefMut};
+#[cfg(feature = "alloc")]
+use sort::apply_permutation;
 use layouts::slice::{Slice, SlicePtr};
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
 
 pub struct Parallel<Innards> {
     _marker: PhantomData<Innards>
 }
 
-unsafe impl<L, R, LLayout, RLayout> ArrayLayout<(L, R)> for Parallel<(LLayout, RLayout)> where LLayout: ArrayLayout<L>, RLayout: ArrayLayout<R> {
-    type Ptr = (LLayout::Ptr, RLayout::Ptr);
-    type ArrayInfo = (LLayout::ArrayInfo, RLayout::ArrayInfo, usize);
-
-    fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
-        let (l_layout, l_info) = LLayout::layout_array(count);
-        let (r_layout, r_info) = RLayout::layout_array(count);
-        let (combined_layout, offset) = l_layout.extend(r_layout).expect("Overflow in combining array layouts");
-        (combined_layout, (l_info, r_info, offset))
-    }
-
-    unsafe fn from_flat_ptr(ptr: NonNull<u8>, (l_info, r_info, offset): Self::ArrayInfo) -> Self::Ptr {
-        (LLayout::from_flat_ptr(ptr, l_info), RLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().offset(offset as isize)), r_info))
-    }
-
-    unsafe fn initialize((l_ptr, r_ptr): Self::Ptr, count: usize) {
-        LLayout::initialize(l_ptr, count);
-        RLayout::initialize(r_ptr, count);
-    }
-
-    unsafe fn base_ptr((l_ptr, _): Self::Ptr, (l_info, _, _): Self::ArrayInfo) -> NonNull<u8> {
-        LLayout::base_ptr(l_ptr, l_info)
-    }
-
-    fn dangling() -> Self::Ptr {
-        (LLayout::dangling(), RLayout::dangling())
-    }
-
-    unsafe fn offset((l_ptr, r_ptr): Self::Ptr, offset: isize) -> Self::Ptr {
-        (LLayout::offset(l_ptr, offset), RLayout::offset(r_ptr, offset))
-    }
-
-    unsafe fn same_ptr((l_ptr1, r_ptr1): Self::Ptr, (l_ptr2, r_ptr2): Self::Ptr) -> bool {
-        if core::mem::size_of::<L>() > 0 {
-            LLayout::same_ptr(l_ptr1, l_ptr2)
-        } else {
-            RLayout::same_ptr(r_ptr1, r_ptr2)
+macro_rules! parallel_impl {
+    { $First:ident; $($T:ident $TLayout:ident $val:ident $idx:tt),+ } => {
+        unsafe impl<$($T, $TLayout),+> ArrayLayout<($($T,)+)> for Parallel<($($TLayout,)+)> where $($TLayout: ArrayLayout<$T>),+ {
+            type Ptr = ($($TLayout::Ptr,)+);
+            type ArrayInfo = ($(($TLayout::ArrayInfo, usize),)+);
+
+            fn layout_array(count: usize) -> (Layout, Self::ArrayInfo) {
+                let combined_layout = Layout::new::<()>();
+                $(
+                    let ($val, combined_layout) = {
+                        let (layout, info) = $TLayout::layout_array(count);
+                        let (combined_layout, offset) = combined_layout.extend(layout).expect("Overflow in combining array layouts");
+                        ((info, offset), combined_layout)
+                    };
+                )+
+                (combined_layout, ($($val,)+))
+            }
+
+            unsafe fn from_flat_ptr(ptr: NonNull<u8>, info: Self::ArrayInfo) -> Self::Ptr {
+                ($($TLayout::from_flat_ptr(NonNull::new_unchecked(ptr.as_ptr().add(info.$idx.1)), info.$idx.0),)+)
+            }
+
+            unsafe fn initialize(ptr: Self::Ptr, count: usize) {
+                $($TLayout::initialize(ptr.$idx, count);)+
+            }
+
+            unsafe fn base_ptr(ptr: Self::Ptr, info: Self::ArrayInfo) -> NonNull<u8> {
+                $First::base_ptr(ptr.0, (info.0).0)
+            }
+
+            fn dangling() -> Self::Ptr {
+                ($($TLayout::dangling(),)+)
+            }
+
+            unsafe fn offset(ptr: Self::Ptr, offset: isize) -> Self::Ptr {
+                ($($TLayout::offset(ptr.$idx, offset),)+)
+            }
+
+            unsafe fn same_ptr(ptr1: Self::Ptr, ptr2: Self::Ptr) -> bool {
+                // Zero-sized columns may spuriously claim equality, so defer to
+                // the first column that actually moves.
+                $(
+                    if core::mem::size_of::<$T>() > 0 {
+                        return $TLayout::same_ptr(ptr1.$idx, ptr2.$idx);
+                    }
+                )+
+                true $(&& $TLayout::same_ptr(ptr1.$idx, ptr2.$idx))+
+            }
+
+            unsafe fn read(ptr: Self::Ptr) -> ($($T,)+) {
+                ($($TLayout::read(ptr.$idx),)+)
+            }
+
+            unsafe fn write(ptr: Self::Ptr, value: ($($T,)+)) {
+                $($TLayout::write(ptr.$idx, value.$idx);)+
+            }
+
+            unsafe fn drop_in_place(ptr: Self::Ptr) {
+                $($TLayout::drop_in_place(ptr.$idx);)+
+            }
+
+            fn can_store(value: &($($T,)+)) -> bool {
+                true $(&& $TLayout::can_store(&value.$idx))+
+            }
+
+            unsafe fn copy_one_nonoverlapping(src: Self::Ptr, dest: Self::Ptr) {
+                $($TLayout::copy_one_nonoverlapping(src.$idx, dest.$idx);)+
+            }
+
+            unsafe fn swap_one_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr) {
+                $($TLayout::swap_one_nonoverlapping(ptr1.$idx, ptr2.$idx);)+
+            }
+
+            unsafe fn copy_leftwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                $($TLayout::copy_leftwards(src.$idx, dest.$idx, count);)+
+            }
+
+            unsafe fn copy_rightwards(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                $($TLayout::copy_rightwards(src.$idx, dest.$idx, count);)+
+            }
+
+            unsafe fn copy_nonoverlapping(src: Self::Ptr, dest: Self::Ptr, count: usize) {
+                $($TLayout::copy_nonoverlapping(src.$idx, dest.$idx, count);)+
+            }
+
+            unsafe fn swap_nonoverlapping(ptr1: Self::Ptr, ptr2: Self::Ptr, count: usize) {
+                $($TLayout::swap_nonoverlapping(ptr1.$idx, ptr2.$idx, count);)+
+            }
         }
-    }
-
-    unsafe fn read((l_ptr, r_ptr): Self::Ptr) -> (L, R) {
-        (LLayout::read(l_ptr), RLayout::read(r_ptr))
-    }
-
-    unsafe fn write((l_ptr, r_ptr): Self::Ptr, (l_value, r_value): (L, R)) {
-        LLayout::write(l_ptr, l_value);
-        RLayout::write(r_ptr, r_value);
-    }
-
-    unsafe fn drop_in_place((l_ptr, r_ptr): Self::Ptr) {
-        LLayout::drop_in_place(l_ptr);
-        RLayout::drop_in_place(r_ptr);
-    }
-
-    unsafe fn copy_one_nonoverlapping((l_src, r_src): Self::Ptr, (l_dest, r_dest): Self::Ptr) {
-        LLayout::copy_one_nonoverlapping(l_src, l_dest);
-        RLayout::copy_one_nonoverlapping(r_src, r_dest);
-    }
-
-    unsafe fn swap_one_nonoverlapping((l_ptr1, r_ptr1): Self::Ptr, (l_ptr2, r_ptr2): Self::Ptr) {
-        LLayout::swap_one_nonoverlapping(l_ptr1, l_ptr2);
-        RLayout::swap_one_nonoverlapping(r_ptr1, r_ptr2);
-    }
-
-    unsafe fn copy_leftwards((l_src, r_src): Self::Ptr, (l_dest, r_dest): Self::Ptr, count: usize) {
-        LLayout::copy_leftwards(l_src, l_dest, count);
-        RLayout::copy_leftwards(r_src, r_dest, count);
-    }
-
-    unsafe fn copy_rightwards((l_src, r_src): Self::Ptr, (l_dest, r_dest): Self::Ptr, count: usize) {
-        LLayout::copy_rightwards(l_src, l_dest, count);
-        RLayout::copy_rightwards(r_src, r_dest, count);
-    }
-
-    unsafe fn copy_nonoverlapping((l_src, r_src): Self::Ptr, (l_dest, r_dest): Self::Ptr, count: usize) {
-        LLayout::copy_nonoverlapping(l_src, l_dest, count);
-        RLayout::copy_nonoverlapping(r_src, r_dest, count);
-    }
-
-    unsafe fn swap_nonoverlapping((l_ptr1, r_ptr1): Self::Ptr, (l_ptr2, r_ptr2): Self::Ptr, count: usize) {
-        LLayout::swap_nonoverlapping(l_ptr1, l_ptr2, count);
-        RLayout::swap_nonoverlapping(r_ptr1, r_ptr2, count);
-    }
-}
 
-impl<'a, L, R, LLayout, RLayout> Ref<'a, [(L, R)], Slice<Parallel<(LLayout, RLayout)>>> where LLayout: ArrayLayout<L>, RLayout: ArrayLayout<R> {
-    pub fn unzip(self) -> (Ref<'a, [L], Slice<LLayout>>, Ref<'a, [R], Slice<RLayout>>) {
-        unsafe { (
-            Ref::from_raw(SlicePtr::from_raw_parts(
-                self.as_ptr().0,
-                self.len()
-            )),
-            Ref::from_raw(SlicePtr::from_raw_parts(
-                self.as_ptr().1,
-                self.len()
-            ))
-        ) }
-    }
-}
+        unsafe impl<$($T: Copy, $TLayout),+> Inspect<($($T,)+)> for Parallel<($($TLayout,)+)> where $($TLayout: ArrayLayout<$T>),+ {
+            unsafe fn inspect<Ret, Func: FnOnce(&($($T,)+)) -> Ret>(ptr: Self::Ptr, func: Func) -> Ret {
+                func(&Self::read(ptr))
+            }
+        }
 
-impl<'a, L, R, LLayout, RLayout> RefMut<'a, [(L, R)], Slice<Parallel<(LLayout, RLayout)>>> where LLayout: ArrayLayout<L>, RLayout: ArrayLayout<R> {
-    pub fn unzip(self) -> (RefMut<'a, [L], Slice<LLayout>>, RefMut<'a, [R], Slice<RLayout>>) {
-        unsafe { (
-            RefMut::from_raw(SlicePtr::from_raw_parts(
-                self.as_ptr().0,
-                self.len()
-            )),
-            RefMut::from_raw(SlicePtr::from_raw_parts(
-                self.as_ptr().1,
-                self.len()
-            ))
-        ) }
-    }
+        impl<'a, $($T, $TLayout),+> Ref<'a, ($($T,)+), Parallel<($($TLayout,)+)>> where $($TLayout: ArrayLayout<$T>),+ {
+            pub fn split(self) -> ($(Ref<'a, $T, $TLayout>,)+) {
+                unsafe { ($(Ref::from_raw(self.as_raw().$idx),)+) }
+            }
+        }
+
+        impl<'a, $($T, $TLayout),+> RefMut<'a, ($($T,)+), Parallel<($($TLayout,)+)>> where $($TLayout: ArrayLayout<$T>),+ {
+            pub fn split(self) -> ($(RefMut<'a, $T, $TLayout>,)+) {
+                unsafe { ($(RefMut::from_raw(self.as_raw().$idx),)+) }
+            }
+        }
+
+        impl<'a, $($T, $TLayout),+> Ref<'a, [($($T,)+)], Slice<Parallel<($($TLayout,)+)>>> where $($TLayout: ArrayLayout<$T>),+ {
+            pub fn unzip(self) -> ($(Ref<'a, [$T], Slice<$TLayout>>,)+) {
+                unsafe { ($(
+                    Ref::from_raw(SlicePtr::from_raw_parts(
+                        self.as_ptr().$idx,
+                        self.len()
+                    )),
+                )+) }
+            }
+        }
+
+        impl<'a, $($T, $TLayout),+> RefMut<'a, [($($T,)+)], Slice<Parallel<($($TLayout,)+)>>> where $($TLayout: ArrayLayout<$T>),+ {
+            pub fn unzip(self) -> ($(RefMut<'a, [$T], Slice<$TLayout>>,)+) {
+                unsafe { ($(
+                    RefMut::from_raw(SlicePtr::from_raw_parts(
+                        self.as_ptr().$idx,
+                        self.len()
+                    )),
+                )+) }
+            }
+
+            // Stable sort that only compares through a permutation of
+            // indices, then walks the cycles of that permutation once per
+            // column, so each column is rearranged with at most `len` swaps.
+            #[cfg(feature = "alloc")]
+            pub fn sort_columns_by<Cmp>(&mut self, mut compare: Cmp)
+                    where Cmp: FnMut(Ref<($($T,)+), Parallel<($($TLayout,)+)>>, Ref<($($T,)+), Parallel<($($TLayout,)+)>>) -> Ordering {
+                let len = self.len();
+                let base = self.as_ptr();
+                let mut perm: Vec<usize> = (0..len).collect();
+                perm.sort_by(|&i, &j| unsafe {
+                    compare(
+                        Ref::from_raw(Parallel::<($($TLayout,)+)>::offset(base, i as isize)),
+                        Ref::from_raw(Parallel::<($($TLayout,)+)>::offset(base, j as isize))
+                    )
+                });
+
+                let mut visited = vec![false; len];
+                $(
+                    for flag in visited.iter_mut() {
+                        *flag = false;
+                    }
+                    unsafe {
+                        apply_permutation::<$T, $TLayout>(base.$idx, &perm, &mut visited);
+                    }
+                )+
+            }
+
+            #[cfg(feature = "alloc")]
+            pub fn sort_columns_by_key<Key: Ord, KeyFn>(&mut self, mut f: KeyFn)
+                    where KeyFn: FnMut(Ref<($($T,)+), Parallel<($($TLayout,)+)>>) -> Key {
+                self.sort_columns_by(|a, b| f(a).cmp(&f(b)));
+            }
+        }
+    };
 }
+
+parallel_impl!{ ALayout; A ALayout a 0 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6, H HLayout h 7 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6, H HLayout h 7, I ILayout i 8 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6, H HLayout h 7, I ILayout i 8, J JLayout j 9 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6, H HLayout h 7, I ILayout i 8, J JLayout j 9, K KLayout k 10 }
+parallel_impl!{ ALayout; A ALayout a 0, B BLayout b 1, C CLayout c 2, D DLayout d 3, E ELayout e 4, F FLayout f 5, G GLayout g 6, H HLayout h 7, I ILayout i 8, J JLayout j 9, K KLayout k 10, L LLayout l 11 }
@@ -1,9 +1,17 @@
 use core::alloc::Layout;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "nightly")]
+use core::iter::TrustedLen;
+use core::iter::FusedIterator;
 use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
 use core::ptr::NonNull;
 
 use reference::{Ref, RefMut};
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
+use sort::rotate;
 
 pub struct Slice<Inner> {
     _marker: PhantomData<Inner>
@@ -38,6 +46,35 @@ impl<T, TLayout: ArrayLayout<T>> SlicePtr<T, TLayout> {
     pub fn len(&self) -> usize {
         self.count
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+// Converts a range into a half-open interval without checking it against the
+// slice length. Overflowing bounds saturate so that they fail the later check.
+fn resolve_range<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&s) => s,
+        Bound::Excluded(&s) => s.saturating_add(1),
+        Bound::Unbounded => 0
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&e) => e.saturating_add(1),
+        Bound::Excluded(&e) => e,
+        Bound::Unbounded => len
+    };
+    (start, end)
+}
+
+fn check_range<R: RangeBounds<usize>>(range: &R, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = resolve_range(range, len);
+    if start <= end && end <= len {
+        Some((start, end))
+    } else {
+        None
+    }
 }
 
 impl<'a, T, TLayout: ArrayLayout<T>> Ref<'a, [T], Slice<TLayout>> {
@@ -48,6 +85,76 @@ impl<'a, T, TLayout: ArrayLayout<T>> Ref<'a, [T], Slice<TLayout>> {
     pub fn len(&self) -> usize {
         self.as_raw().len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_raw().is_empty()
+    }
+
+    pub fn get(self, index: usize) -> Option<Ref<'a, T, TLayout>> {
+        if index < self.len() {
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    pub unsafe fn get_unchecked(self, index: usize) -> Ref<'a, T, TLayout> {
+        debug_assert!(index < self.len());
+        Ref::from_raw(TLayout::offset(self.as_ptr(), index as isize))
+    }
+
+    pub fn first(self) -> Option<Ref<'a, T, TLayout>> {
+        self.get(0)
+    }
+
+    pub fn last(self) -> Option<Ref<'a, T, TLayout>> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn get_range<R: RangeBounds<usize>>(self, range: R) -> Option<Self> {
+        check_range(&range, self.len()).map(|(start, end)| unsafe {
+            self.slice_unchecked(start..end)
+        })
+    }
+
+    pub fn slice<R: RangeBounds<usize>>(self, range: R) -> Self {
+        self.get_range(range).expect("Slice range out of bounds")
+    }
+
+    pub unsafe fn slice_unchecked<R: RangeBounds<usize>>(self, range: R) -> Self {
+        let (start, end) = resolve_range(&range, self.len());
+        debug_assert!(start <= end && end <= self.len());
+        Ref::from_raw(SlicePtr::from_raw_parts(
+            TLayout::offset(self.as_ptr(), start as isize),
+            end - start
+        ))
+    }
+
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len());
+        unsafe { self.split_at_unchecked(mid) }
+    }
+
+    pub unsafe fn split_at_unchecked(self, mid: usize) -> (Self, Self) {
+        (self.slice_unchecked(..mid), self.slice_unchecked(mid..))
+    }
+
+    pub fn split_first(self) -> Option<(Ref<'a, T, TLayout>, Self)> {
+        if self.is_empty() {
+            None
+        } else {
+            unsafe { Some((self.get_unchecked(0), self.slice_unchecked(1..))) }
+        }
+    }
+
+    pub fn split_last(self) -> Option<(Ref<'a, T, TLayout>, Self)> {
+        if self.is_empty() {
+            None
+        } else {
+            let last = self.len() - 1;
+            unsafe { Some((self.get_unchecked(last), self.slice_unchecked(..last))) }
+        }
+    }
 }
 
 impl<'a, T, TLayout: ArrayLayout<T>> RefMut<'a, [T], Slice<TLayout>> {
@@ -58,6 +165,226 @@ impl<'a, T, TLayout: ArrayLayout<T>> RefMut<'a, [T], Slice<TLayout>> {
     pub fn len(&self) -> usize {
         self.as_raw().len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_raw().is_empty()
+    }
+
+    pub fn get_mut(self, index: usize) -> Option<RefMut<'a, T, TLayout>> {
+        if index < self.len() {
+            Some(unsafe { self.get_unchecked_mut(index) })
+        } else {
+            None
+        }
+    }
+
+    pub unsafe fn get_unchecked_mut(self, index: usize) -> RefMut<'a, T, TLayout> {
+        debug_assert!(index < self.len());
+        RefMut::from_raw(TLayout::offset(self.as_ptr(), index as isize))
+    }
+
+    pub fn first_mut(self) -> Option<RefMut<'a, T, TLayout>> {
+        self.get_mut(0)
+    }
+
+    pub fn last_mut(self) -> Option<RefMut<'a, T, TLayout>> {
+        match self.len().checked_sub(1) {
+            Some(index) => self.get_mut(index),
+            None => None
+        }
+    }
+
+    pub fn get_range_mut<R: RangeBounds<usize>>(self, range: R) -> Option<Self> {
+        check_range(&range, self.len()).map(|(start, end)| unsafe {
+            self.slice_unchecked_mut(start..end)
+        })
+    }
+
+    pub fn slice_mut<R: RangeBounds<usize>>(self, range: R) -> Self {
+        self.get_range_mut(range).expect("Slice range out of bounds")
+    }
+
+    pub unsafe fn slice_unchecked_mut<R: RangeBounds<usize>>(self, range: R) -> Self {
+        let (start, end) = resolve_range(&range, self.len());
+        debug_assert!(start <= end && end <= self.len());
+        RefMut::from_raw(SlicePtr::from_raw_parts(
+            TLayout::offset(self.as_ptr(), start as isize),
+            end - start
+        ))
+    }
+
+    pub fn split_at_mut(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len());
+        unsafe { self.split_at_mut_unchecked(mid) }
+    }
+
+    pub unsafe fn split_at_mut_unchecked(self, mid: usize) -> (Self, Self) {
+        let ptr = self.as_ptr();
+        let len = self.len();
+        (
+            RefMut::from_raw(SlicePtr::from_raw_parts(ptr, mid)),
+            RefMut::from_raw(SlicePtr::from_raw_parts(TLayout::offset(ptr, mid as isize), len - mid))
+        )
+    }
+
+    pub fn split_first_mut(self) -> Option<(RefMut<'a, T, TLayout>, Self)> {
+        if self.is_empty() {
+            None
+        } else {
+            let (first, rest) = unsafe { self.split_at_mut_unchecked(1) };
+            Some((unsafe { first.get_unchecked_mut(0) }, rest))
+        }
+    }
+
+    pub fn split_last_mut(self) -> Option<(RefMut<'a, T, TLayout>, Self)> {
+        if self.is_empty() {
+            None
+        } else {
+            let last = self.len() - 1;
+            let (rest, last) = unsafe { self.split_at_mut_unchecked(last) };
+            Some((unsafe { last.get_unchecked_mut(0) }, rest))
+        }
+    }
+}
+
+impl<'a, T, TLayout: ArrayLayout<T>> RefMut<'a, [T], Slice<TLayout>> {
+    pub fn swap_indices(&mut self, a: usize, b: usize) {
+        assert!(a < self.len() && b < self.len());
+        if a != b {
+            unsafe {
+                TLayout::swap_one_nonoverlapping(TLayout::offset(self.as_ptr(), a as isize), TLayout::offset(self.as_ptr(), b as isize));
+            }
+        }
+    }
+
+    pub fn reverse(&mut self) {
+        let len = self.len();
+        for index in 0..len / 2 {
+            unsafe {
+                TLayout::swap_one_nonoverlapping(TLayout::offset(self.as_ptr(), index as isize), TLayout::offset(self.as_ptr(), (len - 1 - index) as isize));
+            }
+        }
+    }
+
+    pub fn rotate_left(&mut self, mid: usize) {
+        assert!(mid <= self.len());
+        unsafe {
+            rotate::<T, TLayout>(self.as_ptr(), 0, mid, self.len());
+        }
+    }
+
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len());
+        let mid = self.len() - k;
+        self.rotate_left(mid);
+    }
+
+    pub fn fill(&mut self, value: T) where T: Clone {
+        if let Some((last, rest)) = self.reborrow_mut().split_last_mut() {
+            for mut elem in rest {
+                elem.set(value.clone());
+            }
+            RefMut::replace(last, value);
+        }
+    }
+
+    pub fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        for mut elem in self.reborrow_mut() {
+            elem.set(f());
+        }
+    }
+
+    pub fn copy_within<R: RangeBounds<usize>>(&mut self, src: R, dest: usize) where T: Copy {
+        let (start, end) = check_range(&src, self.len()).expect("Source range out of bounds");
+        let count = end - start;
+        assert!(dest <= self.len() - count, "Destination out of bounds");
+        unsafe {
+            let src_ptr = TLayout::offset(self.as_ptr(), start as isize);
+            let dest_ptr = TLayout::offset(self.as_ptr(), dest as isize);
+            if dest <= start {
+                TLayout::copy_leftwards(src_ptr, dest_ptr, count);
+            } else {
+                TLayout::copy_rightwards(src_ptr, dest_ptr, count);
+            }
+        }
+    }
+
+    pub fn copy_from_slice(&mut self, src: Ref<[T], Slice<TLayout>>) where T: Copy {
+        assert_eq!(self.len(), src.len(), "Source slice length does not match destination");
+        unsafe {
+            TLayout::copy_nonoverlapping(src.as_ptr(), self.as_ptr(), self.len());
+        }
+    }
+
+    // Copies element by element, converting between the two layouts.
+    pub fn copy_from_other_layout<SrcLayout: ArrayLayout<T>>(&mut self, src: Ref<[T], Slice<SrcLayout>>) where T: Copy {
+        assert_eq!(self.len(), src.len(), "Source slice length does not match destination");
+        for (mut dest, src) in self.reborrow_mut().into_iter().zip(src) {
+            dest.set(src.get());
+        }
+    }
+
+    pub fn clone_from_slice<SrcLayout: Inspect<T>>(&mut self, src: Ref<[T], Slice<SrcLayout>>) where T: Clone {
+        assert_eq!(self.len(), src.len(), "Source slice length does not match destination");
+        for (mut dest, src) in self.reborrow_mut().into_iter().zip(src) {
+            dest.set(src.inspect(T::clone));
+        }
+    }
+}
+
+impl<'a, T: fmt::Debug, TLayout: Inspect<T>> fmt::Debug for Ref<'a, [T], Slice<TLayout>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(*self).finish()
+    }
+}
+
+impl<'a, T: fmt::Debug, TLayout: Inspect<T>> fmt::Debug for RefMut<'a, [T], Slice<TLayout>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.reborrow().fmt(f)
+    }
+}
+
+impl<'a, 'b, T, U, TLayout, ULayout> PartialEq<Ref<'b, [U], Slice<ULayout>>> for Ref<'a, [T], Slice<TLayout>>
+        where T: PartialEq<U>, TLayout: Inspect<T>, ULayout: Inspect<U> {
+    fn eq(&self, other: &Ref<'b, [U], Slice<ULayout>>) -> bool {
+        self.len() == other.len() && self.into_iter().zip(*other).all(|(left, right)| left == right)
+    }
+}
+
+impl<'a, T: Eq, TLayout: Inspect<T>> Eq for Ref<'a, [T], Slice<TLayout>> { }
+
+impl<'a, 'b, T, U, TLayout, ULayout> PartialOrd<Ref<'b, [U], Slice<ULayout>>> for Ref<'a, [T], Slice<TLayout>>
+        where T: PartialOrd<U>, TLayout: Inspect<T>, ULayout: Inspect<U> {
+    fn partial_cmp(&self, other: &Ref<'b, [U], Slice<ULayout>>) -> Option<Ordering> {
+        for (left, right) in self.into_iter().zip(*other) {
+            match left.partial_cmp(&right) {
+                Some(Ordering::Equal) => { },
+                non_eq => return non_eq
+            }
+        }
+        self.len().partial_cmp(&other.len())
+    }
+}
+
+impl<'a, T: Ord, TLayout: Inspect<T>> Ord for Ref<'a, [T], Slice<TLayout>> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (left, right) in self.into_iter().zip(*other) {
+            match left.cmp(&right) {
+                Ordering::Equal => { },
+                non_eq => return non_eq
+            }
+        }
+        self.len().cmp(&other.len())
+    }
+}
+
+impl<'a, T: Hash, TLayout: Inspect<T>> Hash for Ref<'a, [T], Slice<TLayout>> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for elem in *self {
+            elem.hash(state);
+        }
+    }
 }
 
 unsafe impl<T, TLayout> ArrayLayout<[T]> for Slice<TLayout> where TLayout: ArrayLayout<T> {
@@ -90,55 +417,91 @@ unsafe impl<T, TLayout> ArrayLayout<[T]> for Slice<TLayout> where TLayout: Array
 
 pub struct SliceIter<'a, T: 'a, TLayout: ArrayLayout<T>> {
     start: TLayout::Ptr,
-    end: TLayout::Ptr,
+    count: usize,
     _marker: PhantomData<&'a [T]>
 }
 
 pub struct SliceIterMut<'a, T: 'a, TLayout: ArrayLayout<T>> {
     start: TLayout::Ptr,
-    end: TLayout::Ptr,
+    count: usize,
     _marker: PhantomData<&'a mut [T]>
 }
 
-impl<'a, T: 'a, TLayout: ArrayLayout<T>> Iterator for SliceIter<'a, T, TLayout> {
-    type Item = Ref<'a, T, TLayout>;
+macro_rules! slice_iter_impl {
+    { $Iter:ident, $Ref:ident } => {
+        impl<'a, T: 'a, TLayout: ArrayLayout<T>> Iterator for $Iter<'a, T, TLayout> {
+            type Item = $Ref<'a, T, TLayout>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if unsafe { TLayout::same_ptr(self.start, self.end) } {
-            None
-        } else {
-            unsafe {
-                let value = Ref::from_raw(self.start);
-                self.start = TLayout::offset(self.start, 1);
-                Some(value)
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.count == 0 {
+                    None
+                } else {
+                    unsafe {
+                        let value = $Ref::from_raw(self.start);
+                        self.start = TLayout::offset(self.start, 1);
+                        self.count -= 1;
+                        Some(value)
+                    }
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.count, Some(self.count))
+            }
+
+            fn count(self) -> usize {
+                self.count
+            }
+
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                let skip = if n < self.count { n } else { self.count };
+                unsafe {
+                    self.start = TLayout::offset(self.start, skip as isize);
+                }
+                self.count -= skip;
+                self.next()
+            }
+
+            fn last(mut self) -> Option<Self::Item> {
+                self.next_back()
             }
         }
-    }
-}
 
-impl<'a, T: 'a, TLayout: ArrayLayout<T>> Iterator for SliceIterMut<'a, T, TLayout> {
-    type Item = RefMut<'a, T, TLayout>;
+        impl<'a, T: 'a, TLayout: ArrayLayout<T>> DoubleEndedIterator for $Iter<'a, T, TLayout> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                if self.count == 0 {
+                    None
+                } else {
+                    self.count -= 1;
+                    unsafe {
+                        Some($Ref::from_raw(TLayout::offset(self.start, self.count as isize)))
+                    }
+                }
+            }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if unsafe { TLayout::same_ptr(self.start, self.end) } {
-            None
-        } else {
-            unsafe {
-                let value = RefMut::from_raw(self.start);
-                self.start = TLayout::offset(self.start, 1);
-                Some(value)
+            fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+                self.count -= if n < self.count { n } else { self.count };
+                self.next_back()
             }
         }
-    }
+
+        impl<'a, T: 'a, TLayout: ArrayLayout<T>> ExactSizeIterator for $Iter<'a, T, TLayout> { }
+        impl<'a, T: 'a, TLayout: ArrayLayout<T>> FusedIterator for $Iter<'a, T, TLayout> { }
+        #[cfg(feature = "nightly")]
+        unsafe impl<'a, T: 'a, TLayout: ArrayLayout<T>> TrustedLen for $Iter<'a, T, TLayout> { }
+    };
 }
 
+slice_iter_impl!{ SliceIter, Ref }
+slice_iter_impl!{ SliceIterMut, RefMut }
+
 impl<'a, T: 'a, TLayout: ArrayLayout<T>> IntoIterator for Ref<'a, [T], Slice<TLayout>> {
     type Item = Ref<'a, T, TLayout>;
     type IntoIter = SliceIter<'a, T, TLayout>;
     fn into_iter(self) -> Self::IntoIter {
         SliceIter {
             start: self.as_raw().base,
-            end: unsafe { TLayout::offset(self.as_raw().base, self.as_raw().count as isize) },
+            count: self.as_raw().count,
             _marker: PhantomData
         }
     }
@@ -150,7 +513,7 @@ impl<'a, T: 'a, TLayout: ArrayLayout<T>> IntoIterator for RefMut<'a, [T], Slice<
     fn into_iter(self) -> Self::IntoIter {
         SliceIterMut {
             start: self.as_raw().base,
-            end: unsafe { TLayout::offset(self.as_raw().base, self.as_raw().count as isize) },
+            count: self.as_raw().count,
             _marker: PhantomData
         }
     }
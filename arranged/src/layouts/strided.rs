@@ -3,7 +3,7 @@ use core::mem::size_of;
 use core::ptr::{self, NonNull};
 
 use reference::{Ref, RefMut};
-use layouts::ArrayLayout;
+use layouts::{ArrayLayout, Inspect};
 use layouts::flat::Flat;
 use layouts::slice::{Slice, SlicePtr};
 
@@ -81,6 +81,12 @@ unsafe impl<T> ArrayLayout<T> for Strided {
     }
 }
 
+unsafe impl<T> Inspect<T> for Strided {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: StridedPtr<T>, func: F) -> R {
+        func(&*ptr.ptr.as_ptr())
+    }
+}
+
 impl<'a, T> Ref<'a, [T], Slice<Flat>> {
     pub fn strided(self) -> Ref<'a, [T], Slice<Strided>> {
         unsafe {
@@ -111,6 +117,25 @@ impl<'a, T> RefMut<'a, [T], Slice<Flat>> {
 
 macro_rules! unzip_impl {
     { $($T:ident $val:ident),+ } => {
+        impl<'a, $($T),+> Ref<'a, ($($T),+), Strided> {
+            pub fn split(self) -> ($(Ref<'a, $T>),+) {
+                unsafe {
+                    let &($(ref $val),+) = self.as_raw().ptr.as_ref();
+                    ($(Ref::from_flat($val)),+)
+                }
+            }
+        }
+
+        impl<'a, $($T),+> RefMut<'a, ($($T),+), Strided> {
+            pub fn split(self) -> ($(RefMut<'a, $T>),+) {
+                unsafe {
+                    let mut this = self.as_raw().ptr;
+                    let &mut ($(ref mut $val),+) = this.as_mut();
+                    ($(RefMut::from_flat($val)),+)
+                }
+            }
+        }
+
         impl<'a, $($T),+> Ref<'a, [($($T),+)], Slice<Strided>> {
             pub fn unzip(self) -> ($(Ref<'a, [$T], Slice<Strided>>),+) {
                 let ($($val),+) = unsafe {
@@ -0,0 +1,104 @@
+use core::alloc::Layout;
+use core::mem::size_of;
+use core::ptr::{self, NonNull};
+
+use reference::{Ref, RefMut};
+use layouts::slice::{Slice, SlicePtr};
+use layouts::{ArrayLayout, Inspect};
+
+// Packs elements back to back with no alignment at all, reading and writing
+// them with unaligned accesses. This allows viewing records inside arbitrary
+// byte buffers, such as packets read off the network.
+pub struct Unaligned {
+    _priv: ()
+}
+
+unsafe impl<T: Copy> ArrayLayout<T> for Unaligned {
+    type Ptr = NonNull<u8>;
+    type ArrayInfo = ();
+
+    fn layout_array(count: usize) -> (Layout, ()) {
+        let size = size_of::<T>().checked_mul(count).expect("Overflow in calculating array layout");
+        (Layout::from_size_align(size, 1).expect("Overflow in calculating array layout"), ())
+    }
+
+    unsafe fn from_flat_ptr(ptr: NonNull<u8>, _info: ()) -> NonNull<u8> {
+        ptr
+    }
+
+    unsafe fn initialize(_ptr: NonNull<u8>, _count: usize) { }
+
+    unsafe fn base_ptr(ptr: NonNull<u8>, _info: ()) -> NonNull<u8> {
+        ptr
+    }
+
+    fn dangling() -> NonNull<u8> {
+        NonNull::dangling()
+    }
+
+    unsafe fn offset(ptr: NonNull<u8>, offset: isize) -> NonNull<u8> {
+        NonNull::new_unchecked(ptr.as_ptr().offset(offset * size_of::<T>() as isize))
+    }
+
+    unsafe fn same_ptr(ptr1: NonNull<u8>, ptr2: NonNull<u8>) -> bool {
+        ptr1 == ptr2
+    }
+
+    unsafe fn read(ptr: NonNull<u8>) -> T {
+        ptr::read_unaligned(ptr.as_ptr() as *const T)
+    }
+
+    unsafe fn write(ptr: NonNull<u8>, value: T) {
+        ptr::write_unaligned(ptr.as_ptr() as *mut T, value);
+    }
+
+    unsafe fn drop_in_place(_ptr: NonNull<u8>) { }
+
+    unsafe fn copy_one_nonoverlapping(src: NonNull<u8>, dest: NonNull<u8>) {
+        ptr::copy_nonoverlapping(src.as_ptr(), dest.as_ptr(), size_of::<T>());
+    }
+
+    unsafe fn swap_one_nonoverlapping(ptr1: NonNull<u8>, ptr2: NonNull<u8>) {
+        ptr::swap_nonoverlapping(ptr1.as_ptr(), ptr2.as_ptr(), size_of::<T>());
+    }
+
+    unsafe fn copy_leftwards(src: NonNull<u8>, dest: NonNull<u8>, count: usize) {
+        ptr::copy(src.as_ptr(), dest.as_ptr(), count * size_of::<T>());
+    }
+
+    unsafe fn copy_rightwards(src: NonNull<u8>, dest: NonNull<u8>, count: usize) {
+        ptr::copy(src.as_ptr(), dest.as_ptr(), count * size_of::<T>());
+    }
+
+    unsafe fn copy_nonoverlapping(src: NonNull<u8>, dest: NonNull<u8>, count: usize) {
+        ptr::copy_nonoverlapping(src.as_ptr(), dest.as_ptr(), count * size_of::<T>());
+    }
+
+    unsafe fn swap_nonoverlapping(ptr1: NonNull<u8>, ptr2: NonNull<u8>, count: usize) {
+        ptr::swap_nonoverlapping(ptr1.as_ptr(), ptr2.as_ptr(), count * size_of::<T>());
+    }
+}
+
+unsafe impl<T: Copy> Inspect<T> for Unaligned {
+    unsafe fn inspect<R, F: FnOnce(&T) -> R>(ptr: NonNull<u8>, func: F) -> R {
+        func(&Self::read(ptr))
+    }
+}
+
+// Views as many whole records as fit in `bytes`, ignoring any trailing bytes.
+// These are unsafe since the caller must ensure that the bytes of every
+// record are a valid `T`.
+impl<'a, T: Copy> Ref<'a, [T], Slice<Unaligned>> {
+    pub unsafe fn from_bytes(bytes: &'a [u8]) -> Self {
+        assert!(size_of::<T>() > 0, "cannot view bytes as zero-sized records");
+        Ref::from_raw(SlicePtr::from_raw_parts(NonNull::from(bytes).cast(), bytes.len() / size_of::<T>()))
+    }
+}
+
+impl<'a, T: Copy> RefMut<'a, [T], Slice<Unaligned>> {
+    pub unsafe fn from_bytes(bytes: &'a mut [u8]) -> Self {
+        assert!(size_of::<T>() > 0, "cannot view bytes as zero-sized records");
+        let len = bytes.len() / size_of::<T>();
+        RefMut::from_raw(SlicePtr::from_raw_parts(NonNull::from(bytes).cast(), len))
+    }
+}
@@ -1,10 +1,21 @@
-#![feature(allocator_api, alloc_layout_extra, dropck_eyepatch)]
+#![cfg_attr(feature = "nightly", feature(trusted_len))]
 #![no_std]
+#![allow(clippy::missing_safety_doc, clippy::redundant_field_names, clippy::type_complexity)]
 
-pub use layouts::{Flat, Parallel, Slice, Strided};
-#[cfg(feature="bitvec")]
-pub use layouts::PackedBits;
-pub use reference::{Ref, RefMut};
+pub use layouts::{Aligned, ArrayOfLanes, BigEndian, Chunked, Fallible, Flat, LittleEndian, Nullable, Parallel, Slice, Strided, Unaligned};
+pub use layouts::{PackedBits, PackedInts};
+pub use reference::{Ref, RefMut, RefGuard};
+
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "derive")]
+extern crate arranged_derive;
+#[cfg(feature = "derive")]
+pub use arranged_derive::ArrayLayout;
 
 pub mod layouts;
 pub mod reference;
+mod search;
+mod sort;
@@ -1,8 +1,11 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
 
-use layouts::{ArrayLayout, Flat, Extra};
+use layouts::{ArrayLayout, Flat, Extra, Inspect};
 
 pub struct Ref<'a, T: ?Sized + 'a, TLayout = Flat> where TLayout: ArrayLayout<T> {
     ptr: TLayout::Ptr,
@@ -14,6 +17,16 @@ pub struct RefMut<'a, T: ?Sized + 'a, TLayout = Flat> where TLayout: ArrayLayout
     _marker: PhantomData<&'a mut T>
 }
 
+// A temporary copy of an element that is written back into place when
+// dropped, for layouts that never hold a `T` in memory. Only `Copy` types are
+// allowed, since the original stays in place meanwhile: leaking the guard
+// then merely loses the modifications.
+pub struct RefGuard<'a, T: Copy + 'a, TLayout = Flat> where TLayout: ArrayLayout<T> {
+    ptr: TLayout::Ptr,
+    value: T,
+    _marker: PhantomData<&'a mut T>
+}
+
 impl<'a, T: ?Sized, TLayout: ArrayLayout<T>> Copy for Ref<'a, T, TLayout> { }
 impl<'a, T: ?Sized, TLayout: ArrayLayout<T>> Clone for Ref<'a, T, TLayout> {
     fn clone(&self) -> Self { *self }
@@ -125,12 +138,129 @@ impl<'a, T> RefMut<'a, T, Flat> {
     }
 }
 
+impl<'a, T, TLayout: Inspect<T>> Ref<'a, T, TLayout> {
+    pub fn inspect<R, F: FnOnce(&T) -> R>(&self, func: F) -> R {
+        unsafe { TLayout::inspect(self.ptr, func) }
+    }
+}
+
+impl<'a, T, TLayout: Inspect<T>> RefMut<'a, T, TLayout> {
+    pub fn inspect<R, F: FnOnce(&T) -> R>(&self, func: F) -> R {
+        unsafe { TLayout::inspect(self.ptr, func) }
+    }
+}
+
+impl<'a, T: fmt::Debug, TLayout: Inspect<T>> fmt::Debug for Ref<'a, T, TLayout> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inspect(|value| value.fmt(f))
+    }
+}
+
+impl<'a, T: fmt::Debug, TLayout: Inspect<T>> fmt::Debug for RefMut<'a, T, TLayout> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.reborrow().fmt(f)
+    }
+}
+
+impl<'a, 'b, T, U, TLayout, ULayout> PartialEq<Ref<'b, U, ULayout>> for Ref<'a, T, TLayout>
+        where T: PartialEq<U>, TLayout: Inspect<T>, ULayout: Inspect<U> {
+    fn eq(&self, other: &Ref<'b, U, ULayout>) -> bool {
+        self.inspect(|left| other.inspect(|right| left == right))
+    }
+}
+
+impl<'a, T: Eq, TLayout: Inspect<T>> Eq for Ref<'a, T, TLayout> { }
+
+impl<'a, 'b, T, U, TLayout, ULayout> PartialOrd<Ref<'b, U, ULayout>> for Ref<'a, T, TLayout>
+        where T: PartialOrd<U>, TLayout: Inspect<T>, ULayout: Inspect<U> {
+    fn partial_cmp(&self, other: &Ref<'b, U, ULayout>) -> Option<Ordering> {
+        self.inspect(|left| other.inspect(|right| left.partial_cmp(right)))
+    }
+}
+
+impl<'a, T: Ord, TLayout: Inspect<T>> Ord for Ref<'a, T, TLayout> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inspect(|left| other.inspect(|right| left.cmp(right)))
+    }
+}
+
+impl<'a, T: Hash, TLayout: Inspect<T>> Hash for Ref<'a, T, TLayout> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inspect(|value| value.hash(state))
+    }
+}
+
+impl<'a, T: Copy, TLayout: ArrayLayout<T>> Ref<'a, T, TLayout> {
+    pub fn get(&self) -> T {
+        unsafe { TLayout::read(self.ptr) }
+    }
+}
+
+impl<'a, T: Copy, TLayout: ArrayLayout<T>> RefMut<'a, T, TLayout> {
+    pub fn get(&self) -> T {
+        unsafe { TLayout::read(self.ptr) }
+    }
+}
+
 impl<'a, T, TLayout: ArrayLayout<T>> RefMut<'a, T, TLayout> {
+    pub fn set(&mut self, value: T) {
+        RefMut::replace(self.reborrow_mut(), value);
+    }
+
+    pub fn try_set(&mut self, value: T) -> Result<(), T> {
+        if !TLayout::can_store(&value) {
+            return Err(value);
+        }
+        self.set(value);
+        Ok(())
+    }
+
     pub fn replace(reference: Self, value: T) -> T {
+        assert!(TLayout::can_store(&value), "Value cannot be stored in this layout");
         unsafe {
             let old = TLayout::read(reference.ptr);
             TLayout::write(reference.ptr, value);
             old
         }
     }
+
+    pub fn guard(reference: Self) -> RefGuard<'a, T, TLayout> where T: Copy {
+        RefGuard {
+            ptr: reference.ptr,
+            value: reference.get(),
+            _marker: PhantomData
+        }
+    }
+
+    pub fn take(reference: Self) -> T where T: Default {
+        RefMut::replace(reference, T::default())
+    }
+
+    pub fn swap(reference1: Self, reference2: RefMut<T, TLayout>) {
+        unsafe {
+            TLayout::swap_one_nonoverlapping(reference1.ptr, reference2.ptr);
+        }
+    }
+}
+
+impl<'a, T: Copy, TLayout: ArrayLayout<T>> Deref for RefGuard<'a, T, TLayout> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: Copy, TLayout: ArrayLayout<T>> DerefMut for RefGuard<'a, T, TLayout> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<'a, T: Copy, TLayout: ArrayLayout<T>> Drop for RefGuard<'a, T, TLayout> {
+    fn drop(&mut self) {
+        assert!(TLayout::can_store(&self.value), "Value cannot be stored in this layout");
+        unsafe {
+            TLayout::write(self.ptr, self.value);
+        }
+    }
 }
@@ -0,0 +1,49 @@
+use core::cmp::Ordering;
+use core::ops::Range;
+
+use layouts::{ArrayLayout, Inspect};
+use layouts::slice::Slice;
+use reference::Ref;
+
+impl<'a, T, TLayout: ArrayLayout<T>> Ref<'a, [T], Slice<TLayout>> {
+    pub fn binary_search(self, value: &T) -> Result<usize, usize> where T: Ord, TLayout: Inspect<T> {
+        self.binary_search_by(|elem| elem.inspect(|elem| elem.cmp(value)))
+    }
+
+    pub fn binary_search_by<F>(self, mut f: F) -> Result<usize, usize> where F: FnMut(Ref<'a, T, TLayout>) -> Ordering {
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match f(unsafe { self.get_unchecked(mid) }) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid)
+            }
+        }
+        Err(low)
+    }
+
+    pub fn binary_search_by_key<K: Ord, F>(self, key: &K, mut f: F) -> Result<usize, usize> where F: FnMut(Ref<'a, T, TLayout>) -> K {
+        self.binary_search_by(|elem| f(elem).cmp(key))
+    }
+
+    pub fn partition_point<P>(self, mut pred: P) -> usize where P: FnMut(Ref<'a, T, TLayout>) -> bool {
+        self.binary_search_by(|elem| if pred(elem) { Ordering::Less } else { Ordering::Greater })
+            .unwrap_or_else(|index| index)
+    }
+
+    pub fn equal_range(self, value: &T) -> Range<usize> where T: Ord, TLayout: Inspect<T> {
+        self.equal_range_by(|elem| elem.inspect(|elem| elem.cmp(value)))
+    }
+
+    pub fn equal_range_by<F>(self, mut f: F) -> Range<usize> where F: FnMut(Ref<'a, T, TLayout>) -> Ordering {
+        let lower = self.partition_point(|elem| f(elem) == Ordering::Less);
+        let upper = lower + self.slice(lower..).partition_point(|elem| f(elem) != Ordering::Greater);
+        lower..upper
+    }
+
+    pub fn equal_range_by_key<K: Ord, F>(self, key: &K, mut f: F) -> Range<usize> where F: FnMut(Ref<'a, T, TLayout>) -> K {
+        self.equal_range_by(|elem| f(elem).cmp(key))
+    }
+}
@@ -0,0 +1,206 @@
+use core::cmp::Ordering;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use layouts::{ArrayLayout, Inspect};
+use layouts::slice::Slice;
+use reference::{Ref, RefMut};
+
+// Below this length, sorting falls back to insertion sort.
+const INSERTION_THRESHOLD: usize = 16;
+
+unsafe fn at<T, TLayout: ArrayLayout<T>>(base: TLayout::Ptr, index: usize) -> TLayout::Ptr {
+    TLayout::offset(base, index as isize)
+}
+
+unsafe fn compare_at<T, TLayout, F>(base: TLayout::Ptr, i: usize, j: usize, compare: &mut F) -> Ordering
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    compare(Ref::from_raw(at::<T, TLayout>(base, i)), Ref::from_raw(at::<T, TLayout>(base, j)))
+}
+
+unsafe fn swap_at<T, TLayout: ArrayLayout<T>>(base: TLayout::Ptr, i: usize, j: usize) {
+    TLayout::swap_one_nonoverlapping(at::<T, TLayout>(base, i), at::<T, TLayout>(base, j));
+}
+
+// Rotates `[start, end)` so that the element at `mid` ends up at `start`,
+// using the Gries-Mills block swap so that every move is a bulk swap.
+pub(crate) unsafe fn rotate<T, TLayout: ArrayLayout<T>>(base: TLayout::Ptr, mut start: usize, mid: usize, mut end: usize) {
+    loop {
+        let left = mid - start;
+        let right = end - mid;
+        if left == 0 || right == 0 {
+            return;
+        }
+        if left == right {
+            TLayout::swap_nonoverlapping(at::<T, TLayout>(base, start), at::<T, TLayout>(base, mid), left);
+            return;
+        } else if left < right {
+            TLayout::swap_nonoverlapping(at::<T, TLayout>(base, start), at::<T, TLayout>(base, end - left), left);
+            end -= left;
+        } else {
+            TLayout::swap_nonoverlapping(at::<T, TLayout>(base, start), at::<T, TLayout>(base, mid), right);
+            start += right;
+        }
+    }
+}
+
+unsafe fn insertion_sort<T, TLayout, F>(base: TLayout::Ptr, start: usize, end: usize, compare: &mut F)
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    for i in start + 1..end {
+        let mut j = i;
+        while j > start && compare_at(base, j - 1, j, compare) == Ordering::Greater {
+            swap_at::<T, TLayout>(base, j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+// Merges the sorted runs `[start, mid)` and `[mid, end)` without a buffer by
+// recursively rotating the halves into place.
+unsafe fn merge<T, TLayout, F>(base: TLayout::Ptr, start: usize, mid: usize, end: usize, compare: &mut F)
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    if start == mid || mid == end || compare_at(base, mid - 1, mid, compare) != Ordering::Greater {
+        return;
+    }
+
+    let (cut1, cut2) = if mid - start >= end - mid {
+        let cut1 = start + (mid - start) / 2;
+        let (mut low, mut high) = (mid, end);
+        while low < high {
+            let probe = low + (high - low) / 2;
+            if compare_at(base, probe, cut1, compare) == Ordering::Less {
+                low = probe + 1;
+            } else {
+                high = probe;
+            }
+        }
+        (cut1, low)
+    } else {
+        let cut2 = mid + (end - mid) / 2;
+        let (mut low, mut high) = (start, mid);
+        while low < high {
+            let probe = low + (high - low) / 2;
+            if compare_at(base, probe, cut2, compare) == Ordering::Greater {
+                high = probe;
+            } else {
+                low = probe + 1;
+            }
+        }
+        (low, cut2)
+    };
+
+    rotate::<T, TLayout>(base, cut1, mid, cut2);
+    let new_mid = cut1 + (cut2 - mid);
+    merge(base, start, cut1, new_mid, compare);
+    merge(base, new_mid, cut2, end, compare);
+}
+
+unsafe fn merge_sort<T, TLayout, F>(base: TLayout::Ptr, start: usize, end: usize, compare: &mut F)
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    if end - start <= INSERTION_THRESHOLD {
+        insertion_sort(base, start, end, compare);
+        return;
+    }
+    let mid = start + (end - start) / 2;
+    merge_sort(base, start, mid, compare);
+    merge_sort(base, mid, end, compare);
+    merge(base, start, mid, end, compare);
+}
+
+unsafe fn sift_down<T, TLayout, F>(base: TLayout::Ptr, mut root: usize, len: usize, compare: &mut F)
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= len {
+            return;
+        }
+        if child + 1 < len && compare_at(base, child, child + 1, compare) == Ordering::Less {
+            child += 1;
+        }
+        if compare_at(base, root, child, compare) != Ordering::Less {
+            return;
+        }
+        swap_at::<T, TLayout>(base, root, child);
+        root = child;
+    }
+}
+
+unsafe fn heap_sort<T, TLayout, F>(base: TLayout::Ptr, len: usize, compare: &mut F)
+        where TLayout: ArrayLayout<T>, F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+    for root in (0..len / 2).rev() {
+        sift_down(base, root, len, compare);
+    }
+    for end in (1..len).rev() {
+        swap_at::<T, TLayout>(base, 0, end);
+        sift_down(base, 0, end, compare);
+    }
+}
+
+// Rearranges the array so that position `i` holds what was at `perm[i]`,
+// following each cycle of the permutation with single swaps.
+#[cfg(feature = "alloc")]
+pub(crate) unsafe fn apply_permutation<T, TLayout: ArrayLayout<T>>(base: TLayout::Ptr, perm: &[usize], visited: &mut [bool]) {
+    for start in 0..perm.len() {
+        if visited[start] {
+            continue;
+        }
+        let mut cur = start;
+        loop {
+            visited[cur] = true;
+            let next = perm[cur];
+            if next == start {
+                break;
+            }
+            swap_at::<T, TLayout>(base, cur, next);
+            cur = next;
+        }
+    }
+}
+
+impl<'a, T, TLayout: ArrayLayout<T>> RefMut<'a, [T], Slice<TLayout>> {
+    pub fn sort(&mut self) where T: Ord, TLayout: Inspect<T> {
+        self.sort_by(|a, b| a.cmp(&b));
+    }
+
+    // Stable and allocation-free: runs are merged in place by rotation,
+    // which takes O(n log² n) element moves rather than O(n log n).
+    pub fn sort_by<F>(&mut self, mut compare: F) where F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+        unsafe {
+            merge_sort(self.as_ptr(), 0, self.len(), &mut compare);
+        }
+    }
+
+    pub fn sort_by_key<K: Ord, F>(&mut self, mut f: F) where F: FnMut(Ref<T, TLayout>) -> K {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    pub fn sort_unstable(&mut self) where T: Ord, TLayout: Inspect<T> {
+        self.sort_unstable_by(|a, b| a.cmp(&b));
+    }
+
+    pub fn sort_unstable_by<F>(&mut self, mut compare: F) where F: FnMut(Ref<T, TLayout>, Ref<T, TLayout>) -> Ordering {
+        unsafe {
+            heap_sort(self.as_ptr(), self.len(), &mut compare);
+        }
+    }
+
+    pub fn sort_unstable_by_key<K: Ord, F>(&mut self, mut f: F) where F: FnMut(Ref<T, TLayout>) -> K {
+        self.sort_unstable_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn sort_by_cached_key<K: Ord, F>(&mut self, mut f: F) where F: FnMut(Ref<T, TLayout>) -> K {
+        let len = self.len();
+        let base = self.as_ptr();
+        let mut keys: Vec<(K, usize)> = (0..len).map(|index| {
+            f(unsafe { Ref::from_raw(at::<T, TLayout>(base, index)) })
+        }).zip(0..len).collect();
+        // The indices are unique, so an unstable sort preserves the original order of equal keys
+        keys.sort_unstable();
+        let perm: Vec<usize> = keys.into_iter().map(|(_, index)| index).collect();
+        let mut visited = vec![false; len];
+        unsafe {
+            apply_permutation::<T, TLayout>(base, &perm, &mut visited);
+        }
+    }
+}